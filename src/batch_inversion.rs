@@ -0,0 +1,82 @@
+//! Montgomery's trick for inverting many field elements at the cost of a
+//! single inversion and three multiplications per element.
+
+use ff::Field;
+
+use crate::multicore::Worker;
+
+/// Replaces every nonzero element of `v` by its inverse. Zeros are left
+/// untouched. Chunks of `v` are inverted in parallel.
+pub fn batch_inversion<F: Field>(v: &mut [F], worker: &Worker) {
+    worker.scope(v.len(), |scope, chunk| {
+        for v in v.chunks_mut(chunk) {
+            scope.spawn(move |_| serial_batch_inversion(v));
+        }
+    });
+}
+
+/// Replaces every nonzero element of `v` by its inverse. Zeros are left
+/// untouched.
+pub fn serial_batch_inversion<F: Field>(v: &mut [F]) {
+    // Prefix products of the nonzero elements
+    let mut prod = Vec::with_capacity(v.len());
+    let mut tmp = F::one();
+    for f in v.iter().filter(|f| !f.is_zero()) {
+        tmp.mul_assign(f);
+        prod.push(tmp);
+    }
+
+    // Invert the product of all nonzero elements
+    tmp = tmp.inverse().unwrap();
+
+    // Walk backwards, peeling off one element at a time
+    for (f, s) in v
+        .iter_mut()
+        .rev()
+        .filter(|f| !f.is_zero())
+        .zip(prod.into_iter().rev().skip(1).chain(Some(F::one())))
+    {
+        // tmp := tmp * f; f := tmp * s = 1/f
+        let mut newtmp = tmp;
+        newtmp.mul_assign(f);
+        *f = tmp;
+        f.mul_assign(&s);
+        tmp = newtmp;
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bls::Fr;
+
+    #[test]
+    fn test_batch_inversion() {
+        let rng = &mut rand::thread_rng();
+        let worker = Worker::new();
+
+        for len in 0..50 {
+            let mut v = (0..len)
+                .map(|i| {
+                    if i % 3 == 0 {
+                        Fr::zero()
+                    } else {
+                        Fr::random(rng)
+                    }
+                })
+                .collect::<Vec<_>>();
+            let expected = v
+                .iter()
+                .map(|f| f.inverse().unwrap_or_else(Fr::zero))
+                .collect::<Vec<_>>();
+
+            let mut serial = v.clone();
+            serial_batch_inversion(&mut serial);
+            assert_eq!(serial, expected);
+
+            batch_inversion(&mut v, &worker);
+            assert_eq!(v, expected);
+        }
+    }
+}
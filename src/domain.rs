@@ -13,22 +13,127 @@
 
 use crate::bls::Engine;
 use ff::{Field, PrimeField, ScalarEngine};
-use groupy::CurveProjective;
+use groupy::{CurveAffine, CurveProjective};
 
-use super::multicore::Worker;
+use super::batch_inversion::batch_inversion;
+use super::multicore::{Waiter, Worker};
+use super::multiexp::{multiexp, FullDensity};
 use super::SynthesisError;
 
 use crate::gpu;
 
 use log::{info, warn};
+#[cfg(feature = "scratch-pool")]
+use std::alloc::Layout;
+use std::any::{Any, TypeId};
+#[cfg(feature = "scratch-pool")]
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+#[cfg(feature = "scratch-pool")]
+use std::mem::{align_of, size_of, ManuallyDrop};
+#[cfg(feature = "self-profile")]
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+#[cfg(feature = "self-profile")]
+use std::time::Duration;
+use std::time::Instant;
 
+/// FFTs with fewer than `2^GPU_FFT_MIN_LOG_N` elements always run on the CPU.
+static GPU_FFT_MIN_LOG_N: AtomicU32 = AtomicU32::new(0);
+
+/// Largest domain (`log_n`) that [`calibrate_fft_threshold`] benchmarks.
+const CALIBRATION_MAX_LOG_N: u32 = 20;
+
+/// Whether element-wise domain operations tune their chunk size, see
+/// [`set_chunk_tuning`].
+static CHUNK_TUNING_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Numbers of tasks per thread the chunk size autotuner tries, in order.
+const CHUNK_TUNING_CANDIDATES: [usize; 4] = [1, 2, 4, 8];
+
+lazy_static::lazy_static! {
+    /// Timings and results of the chunk size autotuner, keyed by `DomainOp` and
+    /// log2 of the number of elements, rounded up.
+    static ref CHUNK_TUNING: Mutex<HashMap<(DomainOp, u32), ChunkTuning>> =
+        Mutex::new(HashMap::new());
+
+    /// The `best` of each `CHUNK_TUNING` entry at its `chunk_tuning_slot`, or
+    /// `0` while it is still being tuned, so that tuned calls don't lock.
+    static ref CHUNK_TUNING_BEST: Vec<AtomicUsize> =
+        (0..DomainOp::COUNT * 64).map(|_| AtomicUsize::new(0)).collect();
+
+    /// The `RootsOfUnity` of each engine's scalar field, keyed by the engine.
+    static ref ROOTS_OF_UNITY: RwLock<HashMap<TypeId, Arc<dyn Any + Send + Sync>>> =
+        RwLock::new(HashMap::new());
+}
+
+/// Checks that the operands of an element-wise operation have the same length.
+/// With the `unchecked` feature the check only runs in debug builds, for
+/// callers that validate their inputs up front.
+macro_rules! assert_same_len {
+    ($a:expr, $b:expr) => {
+        #[cfg(not(feature = "unchecked"))]
+        assert_eq!($a, $b);
+        #[cfg(feature = "unchecked")]
+        debug_assert_eq!($a, $b);
+    };
+}
+
+/// `EvaluationDomain` is `Send + Sync` for every [`Group`], as groups and field
+/// elements are, so a domain can be shared read-only across threads, e.g.
+/// through an `Arc`, and evaluated concurrently.
 pub struct EvaluationDomain<E: ScalarEngine, G: Group<E>> {
     coeffs: Vec<G>,
+    // Number of coefficients before the zero padding, where `extend` appends
+    unpadded_len: usize,
     exp: u32,
     omega: E::Fr,
     omegainv: E::Fr,
     geninv: E::Fr,
     minv: E::Fr,
+    form: Form,
+    twiddles: Option<Vec<E::Fr>>,
+    max_log_tasks: Option<u32>,
+}
+
+/// What the elements of an [`EvaluationDomain`] represent, as of its last
+/// transform.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Form {
+    /// Coefficients of the polynomial. Domains built by `from_coeffs` are
+    /// assumed to be in this form.
+    Coefficients,
+    /// Evaluations over the domain (or a coset of it), in natural order.
+    Evaluations,
+}
+
+/// Where a transform ran, see [`EvaluationDomain::checked_coset_fft`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FftBackend {
+    Gpu,
+    Cpu,
+}
+
+/// The domain and kernel handed back by [`EvaluationDomain::fft_async`] and
+/// [`EvaluationDomain::ifft_async`] once the transform completes.
+pub type AsyncFft<E, G> = (EvaluationDomain<E, G>, Option<gpu::LockedFFTKernel<E>>);
+
+/// Appends coefficients, like `extend_from_slice`, but defers re-padding the
+/// domain to a power of two until it is next used, so that repeated calls stay
+/// cheap. Until then `as_ref` and `as_mut` return the unpadded coefficients,
+/// and methods taking `&self` work on a padded copy. Extending a domain that
+/// was already transformed is a logic error.
+impl<E: Engine> Extend<E::Fr> for EvaluationDomain<E, Scalar<E>> {
+    fn extend<I: IntoIterator<Item = E::Fr>>(&mut self, iter: I) {
+        assert_eq!(self.form, Form::Coefficients);
+
+        let extra = iter.into_iter().map(Scalar).collect::<Vec<_>>();
+        domain_exp::<E>(self.append_start() + extra.len())
+            .expect("too many coefficients for an evaluation domain");
+        self.append(extra);
+    }
 }
 
 impl<E: ScalarEngine, G: Group<E>> AsRef<[G]> for EvaluationDomain<E, G> {
@@ -44,106 +149,557 @@ impl<E: ScalarEngine, G: Group<E>> AsMut<[G]> for EvaluationDomain<E, G> {
 }
 
 impl<E: Engine, G: Group<E>> EvaluationDomain<E, G> {
-    pub fn into_coeffs(self) -> Vec<G> {
+    pub fn into_coeffs(mut self) -> Vec<G> {
+        self.pad();
         self.coeffs
     }
 
-    pub fn from_coeffs(mut coeffs: Vec<G>) -> Result<EvaluationDomain<E, G>, SynthesisError> {
+    pub fn from_coeffs(coeffs: Vec<G>) -> Result<EvaluationDomain<E, G>, SynthesisError> {
+        Self::from_coeffs_padded_to(coeffs, 0)
+    }
+
+    /// Returns the zero polynomial with room for `cap` coefficients (rounded
+    /// up to a power of two), to be filled with `extend` or
+    /// `extend_from_slice` without reallocating. After `extend`, the domain is
+    /// set up for the final size when it is next used.
+    pub fn with_capacity(cap: usize) -> EvaluationDomain<E, G> {
+        let mut coeffs = Vec::with_capacity(cap.next_power_of_two());
+        coeffs.push(G::group_zero());
+        let mut domain = Self::from_padded_coeffs(coeffs, 0, Form::Coefficients);
+        // The zero is padding, `extend` overwrites it
+        domain.unpadded_len = 0;
+        domain
+    }
+
+    /// Like `from_coeffs`, but the domain holds at least `min_len` elements,
+    /// e.g. to build both operands of a multiplication at the same size.
+    pub fn from_coeffs_padded_to(
+        mut coeffs: Vec<G>,
+        min_len: usize,
+    ) -> Result<EvaluationDomain<E, G>, SynthesisError> {
         // Compute the size of our evaluation domain
-        let mut m = 1;
-        let mut exp = 0;
-        while m < coeffs.len() {
-            m *= 2;
-            exp += 1;
+        let unpadded_len = coeffs.len();
+        let exp = domain_exp::<E>(unpadded_len.max(min_len))?;
 
-            // The pairing-friendly curve may not be able to support
-            // large enough (radix2) evaluation domains.
-            if exp >= E::Fr::S {
-                return Err(SynthesisError::PolynomialDegreeTooLarge);
-            }
+        // Extend the coeffs vector with zeroes if necessary
+        coeffs.resize(1 << exp, G::group_zero());
+
+        let mut domain = Self::from_padded_coeffs(coeffs, exp, Form::Coefficients);
+        domain.unpadded_len = unpadded_len;
+        Ok(domain)
+    }
+
+    /// Appends `extra` to the coefficients, so that `extra[0]` becomes the
+    /// coefficient right after the ones the domain was built from, and re-pads
+    /// the domain to the next power of two. Only meaningful before the first
+    /// transform.
+    pub fn extend_from_slice(&mut self, extra: &[G]) -> Result<(), SynthesisError>
+    where
+        G: PartialEq,
+    {
+        domain_exp::<E>(self.append_start() + extra.len())?;
+        self.append(extra.iter().copied());
+        self.pad();
+
+        Ok(())
+    }
+
+    /// Returns the index `extend` and `extend_from_slice` append at: after the
+    /// coefficients the domain was built from, replacing the zero padding,
+    /// unless something was written into the padding since.
+    fn append_start(&self) -> usize
+    where
+        G: PartialEq,
+    {
+        let len = self.degree().map_or(0, |degree| degree + 1);
+        len.max(self.unpadded_len).min(self.coeffs.len())
+    }
+
+    /// Appends `extra` at `append_start`, deferring the padding to `pad`.
+    fn append<I: IntoIterator<Item = G>>(&mut self, extra: I)
+    where
+        G: PartialEq,
+    {
+        let start = self.append_start();
+        self.coeffs.truncate(start);
+        self.coeffs.extend(extra);
+        self.unpadded_len = self.coeffs.len();
+    }
+
+    /// Zero-extends the coefficients to the size of `other`, e.g. to bring both
+    /// operands of `mul_assign` to the same size before transforming them.
+    /// Both domains must be in coefficient form, and `other` can't be smaller
+    /// than `self`.
+    pub fn pad_to_match(&mut self, other: &Self) -> Result<(), SynthesisError> {
+        if self.form != Form::Coefficients || other.form != Form::Coefficients {
+            return Err(SynthesisError::InvalidDomainForm);
         }
-        // Compute omega, the 2^exp primitive root of unity
-        let mut omega = E::Fr::root_of_unity();
-        for _ in exp..E::Fr::S {
-            omega.square();
+        self.pad();
+        let exp = other.padded_exp();
+        let len = 1 << exp;
+        if len < self.coeffs.len() {
+            return Err(SynthesisError::InvalidDomainSize);
         }
 
-        // Extend the coeffs vector with zeroes if necessary
-        coeffs.resize(m, G::group_zero());
+        if len > self.coeffs.len() {
+            let mut coeffs = std::mem::replace(&mut self.coeffs, Vec::new());
+            coeffs.resize(len, G::group_zero());
+            *self = Self::from_padded_coeffs(coeffs, exp, Form::Coefficients);
+        }
 
-        Ok(EvaluationDomain {
-            coeffs,
-            exp,
+        Ok(())
+    }
+
+    /// Like [`EvaluationDomain::from_coeffs`], but errors with
+    /// `SynthesisError::PolynomialDegreeTooLarge` if the domain would need more
+    /// than `2^max_exp` elements, e.g. to fail fast when a circuit grows.
+    pub fn from_coeffs_with_max_exp(
+        coeffs: Vec<G>,
+        max_exp: u32,
+    ) -> Result<EvaluationDomain<E, G>, SynthesisError> {
+        if domain_exp::<E>(coeffs.len())? > max_exp {
+            return Err(SynthesisError::PolynomialDegreeTooLarge);
+        }
+
+        Self::from_coeffs(coeffs)
+    }
+
+    /// Like [`EvaluationDomain::from_coeffs`], but requires `coeffs.len()` to
+    /// already be a power of two instead of padding it.
+    pub fn try_from_coeffs_exact(coeffs: Vec<G>) -> Result<EvaluationDomain<E, G>, SynthesisError> {
+        if !coeffs.len().is_power_of_two() {
+            return Err(SynthesisError::InvalidDomainSize);
+        }
+
+        let exp = coeffs.len().trailing_zeros();
+        if exp >= E::Fr::S {
+            return Err(SynthesisError::PolynomialDegreeTooLarge);
+        }
+
+        Ok(Self::from_padded_coeffs(coeffs, exp, Form::Coefficients))
+    }
+
+    /// Like [`EvaluationDomain::from_coeffs`], but borrows `coeffs` until the
+    /// domain is first mutated, e.g. for polynomials that are mostly evaluated.
+    pub fn from_coeffs_cow(coeffs: &[G]) -> Result<EvaluationDomainCow<'_, E, G>, SynthesisError> {
+        let exp = domain_exp::<E>(coeffs.len())?;
+
+        Ok(EvaluationDomainCow {
+            state: CowState::Borrowed { coeffs, exp },
+        })
+    }
+
+    /// Like [`EvaluationDomain::from_coeffs`], but returns
+    /// `SynthesisError::OutOfMemory` instead of aborting the process if the
+    /// padded coefficients cannot be allocated.
+    pub fn try_from_coeffs(coeffs: Vec<G>) -> Result<EvaluationDomain<E, G>, SynthesisError> {
+        let unpadded_len = coeffs.len();
+        let exp = domain_exp::<E>(unpadded_len)?;
+        let coeffs = try_resize(coeffs, 1 << exp, G::group_zero())?;
+
+        let mut domain = Self::from_padded_coeffs(coeffs, exp, Form::Coefficients);
+        domain.unpadded_len = unpadded_len;
+        Ok(domain)
+    }
+
+    /// Re-pads the coefficients appended by `extend` to a power of two and
+    /// recomputes the domain. Later `extend`s still append after them.
+    fn pad(&mut self) {
+        if self.coeffs.len() != 1 << self.exp {
+            let exp = self.padded_exp();
+            let unpadded_len = self.unpadded_len;
+            let mut coeffs = std::mem::replace(&mut self.coeffs, Vec::new());
+            coeffs.resize(1 << exp, G::group_zero());
+            *self = Self::from_padded_coeffs(coeffs, exp, self.form);
+            self.unpadded_len = unpadded_len;
+        }
+    }
+
+    /// Returns a copy with the padding deferred by `extend` applied, or `None`
+    /// if there is none, for the methods taking `&self`, which can't `pad`.
+    fn padded_copy(&self) -> Option<Self> {
+        if self.coeffs.len() == 1 << self.exp {
+            return None;
+        }
+
+        let exp = self.padded_exp();
+        let mut coeffs = Vec::with_capacity(1 << exp);
+        coeffs.extend_from_slice(&self.coeffs);
+        coeffs.resize(1 << exp, G::group_zero());
+        let mut domain = Self::from_padded_coeffs(coeffs, exp, self.form);
+        domain.unpadded_len = self.unpadded_len;
+        Some(domain)
+    }
+
+    /// Returns `exp` as it will be after `pad`, without copying anything.
+    fn padded_exp(&self) -> u32 {
+        domain_exp::<E>(self.coeffs.len()).expect("checked by `extend`")
+    }
+
+    /// Sets up the domain for `coeffs`, whose length must be `2^exp`, in form
+    /// `form`.
+    fn from_padded_coeffs(coeffs: Vec<G>, exp: u32, form: Form) -> EvaluationDomain<E, G> {
+        debug_assert_eq!(coeffs.len(), 1 << exp);
+
+        // omega, the 2^exp primitive root of unity, only exists up to S
+        debug_assert!(exp <= E::Fr::S);
+        let omega = RootsOfUnity::<E>::get().omega_for_exp(exp);
+
+        EvaluationDomain {
+            unpadded_len: coeffs.len(),
             omega,
             omegainv: omega.inverse().unwrap(),
             geninv: E::Fr::multiplicative_generator().inverse().unwrap(),
-            minv: E::Fr::from_str(&format!("{}", m))
-                .unwrap()
-                .inverse()
-                .unwrap(),
-        })
+            minv: domain_size::<E::Fr>(exp).inverse().unwrap(),
+            coeffs,
+            exp,
+            form,
+            twiddles: None,
+            max_log_tasks: None,
+        }
     }
 
+    #[must_use = "the coefficients are only in evaluation form if the FFT succeeded"]
     pub fn fft(
         &mut self,
         worker: &Worker,
         kern: &mut Option<gpu::LockedFFTKernel<E>>,
     ) -> gpu::GPUResult<()> {
-        best_fft(kern, &mut self.coeffs, worker, &self.omega, self.exp)?;
+        self.fft_with_cancel(worker, kern, None)?;
+        Ok(())
+    }
+
+    /// Like [`EvaluationDomain::fft`], but returns `GPUError::Cancelled` soon
+    /// after `cancel` is set. The coefficients are garbage after a cancellation.
+    #[must_use = "the coefficients are garbage if the FFT failed or was cancelled"]
+    pub fn fft_cancellable(
+        &mut self,
+        worker: &Worker,
+        kern: &mut Option<gpu::LockedFFTKernel<E>>,
+        cancel: &AtomicBool,
+    ) -> gpu::GPUResult<()> {
+        self.fft_with_cancel(worker, kern, Some(cancel))?;
         Ok(())
     }
 
+    fn fft_with_cancel(
+        &mut self,
+        worker: &Worker,
+        kern: &mut Option<gpu::LockedFFTKernel<E>>,
+        cancel: Option<&AtomicBool>,
+    ) -> gpu::GPUResult<FftBackend> {
+        let _timer = ProfileTimer::start(ProfiledMethod::Fft);
+        self.pad();
+        let options = CpuFftOptions {
+            twiddles: self.twiddles.as_deref().map(|powers| Twiddles {
+                powers,
+                inverse: false,
+            }),
+            max_log_tasks: self.max_log_tasks,
+        };
+        let backend = best_fft(
+            kern,
+            &mut self.coeffs,
+            worker,
+            &self.omega,
+            self.exp,
+            cancel,
+            options,
+        )?;
+        self.form = Form::Evaluations;
+        Ok(backend)
+    }
+
+    #[must_use = "the evaluations are only in coefficient form if the inverse FFT succeeded"]
     pub fn ifft(
         &mut self,
         worker: &Worker,
         kern: &mut Option<gpu::LockedFFTKernel<E>>,
     ) -> gpu::GPUResult<()> {
-        best_fft(kern, &mut self.coeffs, worker, &self.omegainv, self.exp)?;
+        self.ifft_with_cancel(worker, kern, None)
+    }
 
-        worker.scope(self.coeffs.len(), |scope, chunk| {
-            let minv = self.minv;
+    /// Like [`EvaluationDomain::ifft`], but returns `GPUError::Cancelled` soon
+    /// after `cancel` is set. The coefficients are garbage after a cancellation.
+    #[must_use = "the evaluations are garbage if the inverse FFT failed or was cancelled"]
+    pub fn ifft_cancellable(
+        &mut self,
+        worker: &Worker,
+        kern: &mut Option<gpu::LockedFFTKernel<E>>,
+        cancel: &AtomicBool,
+    ) -> gpu::GPUResult<()> {
+        self.ifft_with_cancel(worker, kern, Some(cancel))
+    }
 
-            for v in self.coeffs.chunks_mut(chunk) {
-                scope.spawn(move |_| {
-                    for v in v {
-                        v.group_mul_assign(&minv);
-                    }
-                });
-            }
-        });
+    fn ifft_with_cancel(
+        &mut self,
+        worker: &Worker,
+        kern: &mut Option<gpu::LockedFFTKernel<E>>,
+        cancel: Option<&AtomicBool>,
+    ) -> gpu::GPUResult<()> {
+        let _timer = ProfileTimer::start(ProfiledMethod::Ifft);
+        self.pad();
+        // The inverse transform derives its twiddles from the forward ones
+        let options = CpuFftOptions {
+            twiddles: self.twiddles.as_deref().map(|powers| Twiddles {
+                powers,
+                inverse: true,
+            }),
+            max_log_tasks: self.max_log_tasks,
+        };
+        best_fft(
+            kern,
+            &mut self.coeffs,
+            worker,
+            &self.omegainv,
+            self.exp,
+            cancel,
+            options,
+        )?;
+
+        let minv = self.minv;
+        self.map_in_place(worker, |v| v.group_mul_assign(&minv));
+        self.form = Form::Coefficients;
 
         Ok(())
     }
 
+    /// Like [`EvaluationDomain::fft`], on the CPU with the precomputed setup of
+    /// `plan`, which must be for the size of the domain.
+    pub fn fft_with_plan(&mut self, worker: &Worker, plan: &FftPlan<E>) {
+        self.pad();
+        assert_eq!(plan.exp, self.exp);
+        plan.transform(&mut self.coeffs, worker, false);
+        self.form = Form::Evaluations;
+    }
+
+    /// Like [`EvaluationDomain::ifft`], on the CPU with the precomputed setup
+    /// of `plan`, which must be for the size of the domain.
+    pub fn ifft_with_plan(&mut self, worker: &Worker, plan: &FftPlan<E>) {
+        self.pad();
+        assert_eq!(plan.exp, self.exp);
+        plan.transform(&mut self.coeffs, worker, true);
+        self.form = Form::Coefficients;
+    }
+
+    /// Like [`EvaluationDomain::fft`], for builds without the `gpu` feature,
+    /// where there is no kernel to pass and nothing GPU-specific can fail.
+    #[cfg(not(feature = "gpu"))]
+    pub fn try_fft(&mut self, worker: &Worker) -> Result<(), SynthesisError> {
+        Ok(self.fft(worker, &mut None)?)
+    }
+
+    /// Like [`EvaluationDomain::ifft`], see [`EvaluationDomain::try_fft`].
+    #[cfg(not(feature = "gpu"))]
+    pub fn try_ifft(&mut self, worker: &Worker) -> Result<(), SynthesisError> {
+        Ok(self.ifft(worker, &mut None)?)
+    }
+
+    /// Like [`EvaluationDomain::coset_fft`], see [`EvaluationDomain::try_fft`].
+    #[cfg(not(feature = "gpu"))]
+    pub fn try_coset_fft(&mut self, worker: &Worker) -> Result<(), SynthesisError> {
+        Ok(self.coset_fft(worker, &mut None)?)
+    }
+
+    /// Like [`EvaluationDomain::icoset_fft`], see [`EvaluationDomain::try_fft`].
+    #[cfg(not(feature = "gpu"))]
+    pub fn try_icoset_fft(&mut self, worker: &Worker) -> Result<(), SynthesisError> {
+        Ok(self.icoset_fft(worker, &mut None)?)
+    }
+
+    /// Runs [`EvaluationDomain::fft`] on the thread pool and returns immediately.
+    /// The domain and kernel are moved into the task and handed back once the
+    /// transform completes, so the caller can keep working in the meantime.
+    #[must_use = "the transformed domain is only returned through the `Waiter`"]
+    pub fn fft_async(
+        self,
+        worker: &Worker,
+        kern: Option<gpu::LockedFFTKernel<E>>,
+    ) -> Waiter<gpu::GPUResult<AsyncFft<E, G>>>
+    where
+        G: 'static,
+        gpu::LockedFFTKernel<E>: Send,
+    {
+        self.transform_async(worker, kern, Self::fft)
+    }
+
+    /// Runs [`EvaluationDomain::ifft`] on the thread pool and returns immediately,
+    /// see [`EvaluationDomain::fft_async`].
+    #[must_use = "the transformed domain is only returned through the `Waiter`"]
+    pub fn ifft_async(
+        self,
+        worker: &Worker,
+        kern: Option<gpu::LockedFFTKernel<E>>,
+    ) -> Waiter<gpu::GPUResult<AsyncFft<E, G>>>
+    where
+        G: 'static,
+        gpu::LockedFFTKernel<E>: Send,
+    {
+        self.transform_async(worker, kern, Self::ifft)
+    }
+
+    fn transform_async(
+        mut self,
+        worker: &Worker,
+        mut kern: Option<gpu::LockedFFTKernel<E>>,
+        transform: fn(
+            &mut Self,
+            &Worker,
+            &mut Option<gpu::LockedFFTKernel<E>>,
+        ) -> gpu::GPUResult<()>,
+    ) -> Waiter<gpu::GPUResult<AsyncFft<E, G>>>
+    where
+        G: 'static,
+        gpu::LockedFFTKernel<E>: Send,
+    {
+        let task_worker = worker.clone();
+        worker.compute(move || {
+            transform(&mut self, &task_worker, &mut kern)?;
+            Ok((self, kern))
+        })
+    }
+
+    /// Returns the degree of the polynomial, i.e. the largest index with a
+    /// nonzero coefficient, or `None` for the zero polynomial.
+    pub fn degree(&self) -> Option<usize>
+    where
+        G: PartialEq,
+    {
+        self.coeffs.iter().rposition(|c| *c != G::group_zero())
+    }
+
+    /// Checks that all coefficients at index `bound` or above are zero, i.e.
+    /// that the polynomial has degree below `bound`.
+    pub fn assert_degree_below(&self, bound: usize) -> Result<(), SynthesisError>
+    where
+        G: PartialEq,
+    {
+        match self.degree() {
+            Some(degree) if degree >= bound => Err(SynthesisError::PolynomialDegreeTooLarge),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns `true` if both domains hold the same polynomial, ignoring
+    /// trailing zero coefficients, so that domains of different sizes can
+    /// compare equal.
+    pub fn poly_eq(&self, other: &Self) -> bool
+    where
+        G: PartialEq,
+    {
+        let len = |degree: Option<usize>| degree.map_or(0, |degree| degree + 1);
+        self.coeffs[..len(self.degree())] == other.coeffs[..len(other.degree())]
+    }
+
+    /// Returns `true` if all odd-degree coefficients are zero. The zero
+    /// polynomial is even.
+    pub fn is_even(&self) -> bool
+    where
+        G: PartialEq,
+    {
+        self.coeffs
+            .iter()
+            .skip(1)
+            .step_by(2)
+            .all(|c| *c == G::group_zero())
+    }
+
     pub fn distribute_powers(&mut self, worker: &Worker, g: E::Fr) {
-        worker.scope(self.coeffs.len(), |scope, chunk| {
-            for (i, v) in self.coeffs.chunks_mut(chunk).enumerate() {
-                scope.spawn(move |_| {
-                    let mut u = g.pow(&[(i * chunk) as u64]);
-                    for v in v.iter_mut() {
-                        v.group_mul_assign(&u);
-                        u.mul_assign(&g);
-                    }
-                });
+        let _timer = ProfileTimer::start(ProfiledMethod::DistributePowers);
+        self.pad();
+        distribute_powers(&mut self.coeffs, worker, g);
+    }
+
+    /// Runs `f` on every coefficient, in parallel.
+    pub fn map_in_place<F: Fn(&mut G) + Sync>(&mut self, worker: &Worker, f: F) {
+        self.pad();
+        map_coeffs_in_place(&mut self.coeffs, worker, f);
+    }
+
+    /// Runs `f(i, &mut coeffs[i])` for every coefficient, in parallel.
+    pub fn apply_mut<F: Fn(usize, &mut G) + Sync>(&mut self, worker: &Worker, f: F) {
+        self.pad();
+        let chunk = worker.get_chunk_size(self.coeffs.len());
+        worker.in_place_scope(&mut self.coeffs, |i, v| {
+            for (j, v) in v.iter_mut().enumerate() {
+                f(i * chunk + j, v);
             }
         });
     }
 
+    #[must_use = "the coefficients are only evaluated on the coset if the FFT succeeded"]
     pub fn coset_fft(
         &mut self,
         worker: &Worker,
         kern: &mut Option<gpu::LockedFFTKernel<E>>,
     ) -> gpu::GPUResult<()> {
+        let _timer = ProfileTimer::start(ProfiledMethod::CosetFft);
         self.distribute_powers(worker, E::Fr::multiplicative_generator());
         self.fft(worker, kern)?;
         Ok(())
     }
 
+    /// Like [`EvaluationDomain::coset_fft`], but reports whether the transform
+    /// ran on the GPU or fell back to the CPU, e.g. because the domain is
+    /// below `gpu_fft_threshold` or doesn't fit into the kernel.
+    #[must_use = "this reports which backend ran the coset FFT, or why it failed"]
+    pub fn checked_coset_fft(
+        &mut self,
+        worker: &Worker,
+        kern: &mut Option<gpu::LockedFFTKernel<E>>,
+    ) -> gpu::GPUResult<FftBackend> {
+        let _timer = ProfileTimer::start(ProfiledMethod::CosetFft);
+        self.distribute_powers(worker, E::Fr::multiplicative_generator());
+        self.fft_with_cancel(worker, kern, None)
+    }
+
+    /// Builds a domain from `coeffs` and transforms it with `coset_fft`, so it
+    /// starts out holding the evaluations over the coset.
+    pub fn new_coset(
+        coeffs: Vec<G>,
+        worker: &Worker,
+        kern: &mut Option<gpu::LockedFFTKernel<E>>,
+    ) -> Result<EvaluationDomain<E, G>, SynthesisError> {
+        let mut domain = Self::from_coeffs(coeffs)?;
+        domain.coset_fft(worker, kern)?;
+
+        Ok(domain)
+    }
+
+    /// Returns the evaluations `coset_fft` would compute, leaving `self`
+    /// untouched.
+    #[must_use = "this returns the coset evaluations and leaves `self` untouched"]
+    pub fn cloned_coset_fft(
+        &self,
+        worker: &Worker,
+        kern: &mut Option<gpu::LockedFFTKernel<E>>,
+    ) -> gpu::GPUResult<Vec<G>> {
+        if let Some(mut padded) = self.padded_copy() {
+            padded.coset_fft(worker, kern)?;
+            return Ok(padded.coeffs);
+        }
+
+        let mut coeffs = self.coeffs.clone();
+        distribute_powers(&mut coeffs, worker, E::Fr::multiplicative_generator());
+        best_fft(
+            kern,
+            &mut coeffs,
+            worker,
+            &self.omega,
+            self.exp,
+            None,
+            CpuFftOptions::default(),
+        )?;
+        Ok(coeffs)
+    }
+
+    #[must_use = "the evaluations are only interpolated back if the inverse coset FFT succeeded"]
     pub fn icoset_fft(
         &mut self,
         worker: &Worker,
         kern: &mut Option<gpu::LockedFFTKernel<E>>,
     ) -> gpu::GPUResult<()> {
+        let _timer = ProfileTimer::start(ProfiledMethod::IcosetFft);
         let geninv = self.geninv;
         self.ifft(worker, kern)?;
         self.distribute_powers(worker, geninv);
@@ -153,424 +709,5931 @@ impl<E: Engine, G: Group<E>> EvaluationDomain<E, G> {
     /// This evaluates t(tau) for this domain, which is
     /// tau^m - 1 for these radix-2 domains.
     pub fn z(&self, tau: &E::Fr) -> E::Fr {
-        let mut tmp = tau.pow(&[self.coeffs.len() as u64]);
+        let mut tmp = tau.pow(&[1 << self.padded_exp()]);
         tmp.sub_assign(&E::Fr::one());
 
         tmp
     }
 
+    /// Returns the inverse of `z(tau)`, or `SynthesisError::DivisionByZero` if
+    /// `tau` is a root of the domain and `z(tau)` is zero.
+    pub fn checked_z_inverse(&self, tau: &E::Fr) -> Result<E::Fr, SynthesisError> {
+        self.z(tau).inverse().ok_or(SynthesisError::DivisionByZero)
+    }
+
+    /// Evaluates the derivative of the vanishing polynomial at the root
+    /// `omega^k`, which is `z'(omega^k) = m * omega^{-k}`.
+    pub fn z_derivative_at_root(&self, k: usize) -> E::Fr {
+        if let Some(padded) = self.padded_copy() {
+            return padded.z_derivative_at_root(k);
+        }
+
+        let m = 1 << self.exp;
+        let mut tmp = self.omegainv.pow(&[(k % m) as u64]);
+        tmp.mul_assign(&domain_size::<E::Fr>(self.exp));
+
+        tmp
+    }
+
+    /// Returns the coefficients of the vanishing polynomial `x^m - 1` that `z`
+    /// evaluates, i.e. `-1` at index `0`, `1` at index `m` and zeros elsewhere.
+    pub fn vanishing_poly_coeffs(&self) -> Vec<E::Fr> {
+        let m = 1 << self.padded_exp();
+        let mut coeffs = vec![E::Fr::zero(); m + 1];
+        coeffs[0] = E::Fr::one();
+        coeffs[0].negate();
+        coeffs[m] = E::Fr::one();
+        coeffs
+    }
+
+    /// Reduces the polynomial (in coefficient form) modulo `x^(2^exp) - 1` and
+    /// shrinks the domain to size `2^exp`. Since `x^(2^exp) = 1` there, every
+    /// coefficient at index `i` is added onto index `i mod 2^exp`. Errors if
+    /// `exp` is larger than the current domain.
+    pub fn reduce_mod_vanishing(&mut self, exp: u32) -> Result<(), SynthesisError> {
+        self.check_form(Form::Coefficients)?;
+        self.pad();
+        if exp > self.exp {
+            return Err(SynthesisError::InvalidDomainSize);
+        }
+
+        let m = 1 << exp;
+        let mut coeffs = std::mem::replace(&mut self.coeffs, vec![]);
+        let (low, high) = coeffs.split_at_mut(m);
+        for high in high.chunks(m) {
+            for (low, high) in low.iter_mut().zip(high.iter()) {
+                low.group_add_assign(high);
+            }
+        }
+        coeffs.truncate(m);
+
+        *self = Self::from_padded_coeffs(coeffs, exp, self.form);
+        Ok(())
+    }
+
     /// The target polynomial is the zero polynomial in our
     /// evaluation domain, so we must perform division over
     /// a coset.
     pub fn divide_by_z_on_coset(&mut self, worker: &Worker) {
+        let _timer = ProfileTimer::start(ProfiledMethod::DivideByZOnCoset);
+        self.pad();
+        // The generator of the coset is never a root of the domain
         let i = self
-            .z(&E::Fr::multiplicative_generator())
-            .inverse()
-            .unwrap();
+            .checked_z_inverse(&E::Fr::multiplicative_generator())
+            .expect("z is nonzero on the coset");
 
-        worker.scope(self.coeffs.len(), |scope, chunk| {
-            for v in self.coeffs.chunks_mut(chunk) {
-                scope.spawn(move |_| {
-                    for v in v {
-                        v.group_mul_assign(&i);
-                    }
-                });
-            }
-        });
+        tuned_scope(
+            worker,
+            DomainOp::DivideByZ,
+            self.coeffs.len(),
+            |scope, chunk| {
+                for v in self.coeffs.chunks_mut(chunk) {
+                    scope.spawn(move |_| {
+                        for v in v {
+                            v.group_mul_assign(&i);
+                        }
+                    });
+                }
+            },
+        );
+    }
+
+    /// Replaces the polynomial (in coefficient form) by its product with
+    /// `other_on_coset`, divided by `z`. `other_on_coset` holds the
+    /// evaluations computed by `coset_fft`, and the product must be divisible
+    /// by `z`. All of the work happens on the coset, where `z` has no roots.
+    #[must_use = "`self` only holds the quotient if both coset transforms succeeded"]
+    pub fn coset_quotient(
+        &mut self,
+        worker: &Worker,
+        kern: &mut Option<gpu::LockedFFTKernel<E>>,
+        other_on_coset: &EvaluationDomain<E, Scalar<E>>,
+    ) -> gpu::GPUResult<()> {
+        self.coset_fft(worker, kern)?;
+        self.mul_assign(worker, other_on_coset);
+        self.divide_by_z_on_coset(worker);
+        self.icoset_fft(worker, kern)
     }
 
     /// Perform O(n) multiplication of two polynomials in the domain.
     pub fn mul_assign(&mut self, worker: &Worker, other: &EvaluationDomain<E, Scalar<E>>) {
-        assert_eq!(self.coeffs.len(), other.coeffs.len());
+        if let Some(other) = other.padded_copy() {
+            return self.mul_assign(worker, &other);
+        }
+        let _timer = ProfileTimer::start(ProfiledMethod::MulAssign);
+        self.pad();
+        assert_same_len!(self.coeffs.len(), other.coeffs.len());
 
-        worker.scope(self.coeffs.len(), |scope, chunk| {
-            for (a, b) in self
-                .coeffs
-                .chunks_mut(chunk)
-                .zip(other.coeffs.chunks(chunk))
-            {
-                scope.spawn(move |_| {
-                    for (a, b) in a.iter_mut().zip(b.iter()) {
-                        a.group_mul_assign(&b.0);
-                    }
-                });
-            }
-        });
+        tuned_scope(
+            worker,
+            DomainOp::MulAssign,
+            self.coeffs.len(),
+            |scope, chunk| {
+                for (a, b) in self
+                    .coeffs
+                    .chunks_mut(chunk)
+                    .zip(other.coeffs.chunks(chunk))
+                {
+                    scope.spawn(move |_| {
+                        for (a, b) in a.iter_mut().zip(b.iter()) {
+                            a.group_mul_assign(&b.0);
+                        }
+                    });
+                }
+            },
+        );
     }
 
-    /// Perform O(n) subtraction of one polynomial from another in the domain.
-    pub fn sub_assign(&mut self, worker: &Worker, other: &EvaluationDomain<E, G>) {
-        assert_eq!(self.coeffs.len(), other.coeffs.len());
+    /// Like `mul_assign`, but the evaluations in `self` and `other` may be over
+    /// domains of different sizes. The smaller operand is brought to the size
+    /// of the larger one first, which costs an `ifft` and an `fft` of the
+    /// larger size (on a copy, if it is `other`).
+    pub fn mul_assign_resized(
+        &mut self,
+        worker: &Worker,
+        kern: &mut Option<gpu::LockedFFTKernel<E>>,
+        other: &EvaluationDomain<E, Scalar<E>>,
+    ) -> gpu::GPUResult<()> {
+        self.pad();
+        if let Some(other) = other.padded_copy() {
+            return self.mul_assign_resized(worker, kern, &other);
+        }
 
-        worker.scope(self.coeffs.len(), |scope, chunk| {
-            for (a, b) in self
-                .coeffs
-                .chunks_mut(chunk)
-                .zip(other.coeffs.chunks(chunk))
-            {
-                scope.spawn(move |_| {
-                    for (a, b) in a.iter_mut().zip(b.iter()) {
-                        a.group_sub_assign(&b);
-                    }
-                });
-            }
-        });
+        if self.exp < other.exp {
+            self.resize_evaluations(other.exp, worker, kern)?;
+        } else if other.exp < self.exp {
+            let mut other =
+                EvaluationDomain::from_padded_coeffs(other.coeffs.clone(), other.exp, other.form);
+            other.resize_evaluations(self.exp, worker, kern)?;
+            self.mul_assign(worker, &other);
+            return Ok(());
+        }
+
+        self.mul_assign(worker, other);
+        Ok(())
     }
-}
 
-pub trait Group<E: ScalarEngine>: Sized + Copy + Clone + Send + Sync {
-    fn group_zero() -> Self;
-    fn group_mul_assign(&mut self, by: &E::Fr);
-    fn group_add_assign(&mut self, other: &Self);
-    fn group_sub_assign(&mut self, other: &Self);
-}
+    /// Re-evaluates the polynomial over a larger domain of size `2^exp`.
+    fn resize_evaluations(
+        &mut self,
+        exp: u32,
+        worker: &Worker,
+        kern: &mut Option<gpu::LockedFFTKernel<E>>,
+    ) -> gpu::GPUResult<()> {
+        self.ifft(worker, kern)?;
+        let mut coeffs = std::mem::replace(&mut self.coeffs, Vec::new());
+        coeffs.resize(1 << exp, G::group_zero());
+        *self = Self::from_padded_coeffs(coeffs, exp, Form::Coefficients);
+        self.fft(worker, kern)
+    }
 
-pub struct Point<G: CurveProjective>(pub G);
+    /// Perform O(n) addition of one polynomial to another in the domain.
+    pub fn add_assign(&mut self, worker: &Worker, other: &EvaluationDomain<E, G>) {
+        if let Some(other) = other.padded_copy() {
+            return self.add_assign(worker, &other);
+        }
+        let _timer = ProfileTimer::start(ProfiledMethod::AddAssign);
+        self.pad();
+        assert_same_len!(self.coeffs.len(), other.coeffs.len());
 
-impl<G: CurveProjective> PartialEq for Point<G> {
-    fn eq(&self, other: &Point<G>) -> bool {
-        self.0 == other.0
+        tuned_scope(
+            worker,
+            DomainOp::AddAssign,
+            self.coeffs.len(),
+            |scope, chunk| {
+                for (a, b) in self
+                    .coeffs
+                    .chunks_mut(chunk)
+                    .zip(other.coeffs.chunks(chunk))
+                {
+                    scope.spawn(move |_| {
+                        for (a, b) in a.iter_mut().zip(b.iter()) {
+                            a.group_add_assign(&b);
+                        }
+                    });
+                }
+            },
+        );
     }
-}
 
-impl<G: CurveProjective> Copy for Point<G> {}
+    /// Perform O(n) subtraction of one polynomial from another in the domain.
+    pub fn sub_assign(&mut self, worker: &Worker, other: &EvaluationDomain<E, G>) {
+        if let Some(other) = other.padded_copy() {
+            return self.sub_assign(worker, &other);
+        }
+        let _timer = ProfileTimer::start(ProfiledMethod::SubAssign);
+        self.pad();
+        assert_same_len!(self.coeffs.len(), other.coeffs.len());
 
-impl<G: CurveProjective> Clone for Point<G> {
-    fn clone(&self) -> Point<G> {
-        *self
+        tuned_scope(
+            worker,
+            DomainOp::SubAssign,
+            self.coeffs.len(),
+            |scope, chunk| {
+                for (a, b) in self
+                    .coeffs
+                    .chunks_mut(chunk)
+                    .zip(other.coeffs.chunks(chunk))
+                {
+                    scope.spawn(move |_| {
+                        for (a, b) in a.iter_mut().zip(b.iter()) {
+                            a.group_sub_assign(&b);
+                        }
+                    });
+                }
+            },
+        );
     }
-}
 
-impl<G: CurveProjective> Group<G::Engine> for Point<G> {
-    fn group_zero() -> Self {
-        Point(G::zero())
+    /// Estimates the number of bytes of RAM used by a domain holding at least
+    /// `min_len` coefficients, including the temporaries `fft`/`ifft` allocate
+    /// on the CPU with the current number of threads.
+    pub fn estimate_memory(min_len: usize) -> Result<usize, SynthesisError> {
+        let exp = domain_exp::<E>(min_len)?;
+        let coeffs = (1usize << exp) * std::mem::size_of::<G>();
+
+        // `parallel_fft` allocates a second copy of the coefficients, split
+        // across the threads.
+        let scratch = match planned_log_cpus(&Worker::new(), exp, None) {
+            0 => 0,
+            _ => coeffs,
+        };
+
+        Ok(coeffs + scratch)
     }
-    fn group_mul_assign(&mut self, by: &G::Scalar) {
-        self.0.mul_assign(by.into_repr());
+
+    /// Estimates the number of bytes of GPU memory needed to run `fft`/`ifft`
+    /// on a domain holding at least `min_len` coefficients.
+    pub fn estimate_gpu_memory(min_len: usize) -> Result<usize, SynthesisError> {
+        let exp = domain_exp::<E>(min_len)?;
+        Ok(gpu::FFTKernel::<E>::memory_required(1 << exp))
     }
-    fn group_add_assign(&mut self, other: &Self) {
-        self.0.add_assign(&other.0);
+
+    /// Like [`EvaluationDomain::fft`], using [`Worker::current`].
+    #[must_use = "the coefficients are only in evaluation form if the FFT succeeded"]
+    pub fn fft_default(
+        &mut self,
+        kern: &mut Option<gpu::LockedFFTKernel<E>>,
+    ) -> gpu::GPUResult<()> {
+        self.fft(Worker::current(), kern)
     }
-    fn group_sub_assign(&mut self, other: &Self) {
-        self.0.sub_assign(&other.0);
+
+    /// Like [`EvaluationDomain::ifft`], using [`Worker::current`].
+    #[must_use = "the evaluations are only in coefficient form if the inverse FFT succeeded"]
+    pub fn ifft_default(
+        &mut self,
+        kern: &mut Option<gpu::LockedFFTKernel<E>>,
+    ) -> gpu::GPUResult<()> {
+        self.ifft(Worker::current(), kern)
     }
-}
 
-pub struct Scalar<E: ScalarEngine>(pub E::Fr);
+    /// Like [`EvaluationDomain::coset_fft`], using [`Worker::current`].
+    #[must_use = "the coefficients are only evaluated on the coset if the FFT succeeded"]
+    pub fn coset_fft_default(
+        &mut self,
+        kern: &mut Option<gpu::LockedFFTKernel<E>>,
+    ) -> gpu::GPUResult<()> {
+        self.coset_fft(Worker::current(), kern)
+    }
 
-impl<E: ScalarEngine> PartialEq for Scalar<E> {
-    fn eq(&self, other: &Scalar<E>) -> bool {
-        self.0 == other.0
+    /// Like [`EvaluationDomain::icoset_fft`], using [`Worker::current`].
+    #[must_use = "the evaluations are only interpolated back if the inverse coset FFT succeeded"]
+    pub fn icoset_fft_default(
+        &mut self,
+        kern: &mut Option<gpu::LockedFFTKernel<E>>,
+    ) -> gpu::GPUResult<()> {
+        self.icoset_fft(Worker::current(), kern)
     }
-}
 
-impl<E: ScalarEngine> Copy for Scalar<E> {}
+    /// Like [`EvaluationDomain::divide_by_z_on_coset`], using [`Worker::current`].
+    pub fn divide_by_z_on_coset_default(&mut self) {
+        self.divide_by_z_on_coset(Worker::current())
+    }
 
-impl<E: ScalarEngine> Clone for Scalar<E> {
-    fn clone(&self) -> Scalar<E> {
-        *self
+    /// Like [`EvaluationDomain::mul_assign`], using [`Worker::current`].
+    pub fn mul_assign_default(&mut self, other: &EvaluationDomain<E, Scalar<E>>) {
+        self.mul_assign(Worker::current(), other)
     }
-}
 
-impl<E: ScalarEngine> Group<E> for Scalar<E> {
-    fn group_zero() -> Self {
-        Scalar(E::Fr::zero())
+    /// Like [`EvaluationDomain::sub_assign`], using [`Worker::current`].
+    pub fn sub_assign_default(&mut self, other: &EvaluationDomain<E, G>) {
+        self.sub_assign(Worker::current(), other)
     }
-    fn group_mul_assign(&mut self, by: &E::Fr) {
-        self.0.mul_assign(by);
+
+    /// Returns log2 of the number of sub-FFTs (and scratch vectors) a CPU
+    /// `fft`/`ifft` of this domain is split into, `0` if it runs serially.
+    pub fn planned_parallelism(&self, worker: &Worker) -> u32 {
+        planned_log_cpus(worker, self.padded_exp(), self.max_log_tasks)
     }
-    fn group_add_assign(&mut self, other: &Self) {
-        self.0.add_assign(&other.0);
+
+    /// Splits CPU `fft`s and `ifft`s of this domain into at most
+    /// `2^log_tasks` sub-FFTs, instead of one per CPU. Fewer tasks need fewer
+    /// scratch vectors (of `n / 2^log_tasks` elements each) at the cost of
+    /// parallelism. Applies until the domain is resized.
+    pub fn set_max_log_tasks(&mut self, log_tasks: u32) {
+        self.pad();
+        self.max_log_tasks = Some(log_tasks);
     }
-    fn group_sub_assign(&mut self, other: &Self) {
-        self.0.sub_assign(&other.0);
+
+    /// Concatenates the coefficients of two domains of equal size into a
+    /// domain of twice the size, e.g. to join evaluations over two cosets.
+    pub fn concat(mut self, mut other: EvaluationDomain<E, G>) -> Result<Self, SynthesisError> {
+        self.pad();
+        other.pad();
+        if self.coeffs.len() != other.coeffs.len() {
+            return Err(SynthesisError::InvalidDomainSize);
+        }
+        let exp = domain_exp::<E>(2 * self.coeffs.len())?;
+
+        self.coeffs.extend_from_slice(&other.coeffs);
+        Ok(Self::from_padded_coeffs(self.coeffs, exp, self.form))
     }
-}
 
-fn best_fft<E: Engine, T: Group<E>>(
-    kern: &mut Option<gpu::LockedFFTKernel<E>>,
-    a: &mut [T],
-    worker: &Worker,
-    omega: &E::Fr,
-    log_n: u32,
-) -> gpu::GPUResult<()> {
-    if let Some(ref mut kern) = kern {
-        if kern
-            .with(|k: &mut gpu::FFTKernel<E>| gpu_fft(k, a, omega, log_n))
-            .is_ok()
-        {
-            return Ok(());
+    /// Returns the form the elements are in, as of the last transform.
+    pub fn form(&self) -> Form {
+        self.form
+    }
+
+    /// Errors with `SynthesisError::InvalidDomainForm` unless the elements are
+    /// in form `form`.
+    fn check_form(&self, form: Form) -> Result<(), SynthesisError> {
+        if self.form != form {
+            return Err(SynthesisError::InvalidDomainForm);
         }
+        Ok(())
     }
 
-    let log_cpus = worker.log_num_cpus();
-    if log_n <= log_cpus {
-        serial_fft(a, omega, log_n);
-    } else {
-        parallel_fft(a, worker, omega, log_n, log_cpus);
+    /// Returns the roots of unity of the domain, `omega^i` for `i < m`, in the
+    /// order `fft` evaluates at them.
+    pub fn roots(&self, worker: &Worker) -> Vec<E::Fr> {
+        if let Some(padded) = self.padded_copy() {
+            return padded.roots(worker);
+        }
+
+        powers_of(&self.omega, 1 << self.exp, worker)
     }
 
-    Ok(())
-}
+    /// Pairs each root `omega^i` with the evaluation stored at index `i`, e.g.
+    /// to export the evaluations as explicit points.
+    pub fn iter_roots_with_values(
+        &self,
+        worker: &Worker,
+    ) -> Result<Vec<(E::Fr, G)>, SynthesisError> {
+        self.check_form(Form::Evaluations)?;
 
-pub fn gpu_fft<E: Engine, T: Group<E>>(
-    kern: &mut gpu::FFTKernel<E>,
-    a: &mut [T],
-    omega: &E::Fr,
-    log_n: u32,
-) -> gpu::GPUResult<()> {
-    // EvaluationDomain module is supposed to work only with E::Fr elements, and not CurveProjective
-    // points. The Bellman authors have implemented an unnecessarry abstraction called Group<E>
-    // which is implemented for both PrimeField and CurveProjective elements. As nowhere in the code
-    // is the CurveProjective version used, T and E::Fr are guaranteed to be equal and thus have same
-    // size.
-    // For compatibility/performance reasons we decided to transmute the array to the desired type
-    // as it seems safe and needs less modifications in the current structure of Bellman library.
-    let a = unsafe { std::mem::transmute::<&mut [T], &mut [E::Fr]>(a) };
-    kern.radix_fft(a, omega, log_n)?;
-    Ok(())
-}
+        Ok(self
+            .roots(worker)
+            .into_iter()
+            .zip(self.coeffs.iter().cloned())
+            .collect())
+    }
 
-pub fn serial_fft<E: ScalarEngine, T: Group<E>>(a: &mut [T], omega: &E::Fr, log_n: u32) {
-    fn bitreverse(mut n: u32, l: u32) -> u32 {
-        let mut r = 0;
-        for _ in 0..l {
-            r = (r << 1) | (n & 1);
-            n >>= 1;
+    /// Like `into_coeffs`, but errors unless the domain is in coefficient
+    /// form, e.g. because an `fft` wasn't followed by an `ifft`.
+    pub fn try_into_coeffs(self) -> Result<Vec<G>, SynthesisError> {
+        match self.form {
+            Form::Coefficients => Ok(self.into_coeffs()),
+            _ => Err(SynthesisError::InvalidDomainForm),
         }
-        r
     }
 
-    let n = a.len() as u32;
-    assert_eq!(n, 1 << log_n);
+    /// Precomputes and keeps the twiddle factors of this domain, so that
+    /// subsequent CPU `fft`s and `ifft`s don't regenerate them in every stage.
+    /// The inverse transform reuses the same table. Costs `n/2` extra field
+    /// elements of memory until the domain is resized.
+    pub fn precompute_twiddles(&mut self, worker: &Worker) {
+        self.pad();
+        self.twiddles = Some(twiddle_table(&self.omega, self.exp, worker));
+    }
 
-    for k in 0..n {
-        let rk = bitreverse(k, log_n);
-        if k < rk {
-            a.swap(rk as usize, k as usize);
+    /// Brings `self` to the size and form of `other`, so that the two can be
+    /// combined by `mul_assign`, `sub_assign` etc. Resizing goes through
+    /// coefficient form and only shrinks the domain if the polynomial fits
+    /// into the smaller one; otherwise `self` is left in coefficient form.
+    /// Evaluations are assumed to be over the domain rather than a coset.
+    pub fn align_with(
+        &mut self,
+        other: &EvaluationDomain<E, G>,
+        worker: &Worker,
+        kern: &mut Option<gpu::LockedFFTKernel<E>>,
+    ) -> gpu::GPUResult<()>
+    where
+        G: PartialEq,
+    {
+        self.pad();
+        if let Some(other) = other.padded_copy() {
+            return self.align_with(&other, worker, kern);
+        }
+
+        if self.exp != other.exp {
+            if self.form == Form::Evaluations {
+                self.ifft(worker, kern)?;
+            }
+
+            let len = 1 << other.exp;
+            if self.coeffs.iter().skip(len).any(|c| *c != G::group_zero()) {
+                return Err(gpu::GPUError::Simple(
+                    "Polynomial does not fit into the smaller domain!",
+                ));
+            }
+            let mut coeffs = std::mem::replace(&mut self.coeffs, Vec::new());
+            coeffs.resize(len, G::group_zero());
+            *self = Self::from_padded_coeffs(coeffs, other.exp, self.form);
+        }
+
+        match (self.form, other.form) {
+            (Form::Coefficients, Form::Evaluations) => self.fft(worker, kern),
+            (Form::Evaluations, Form::Coefficients) => self.ifft(worker, kern),
+            _ => Ok(()),
         }
     }
 
-    let mut m = 1;
-    for _ in 0..log_n {
-        let w_m = omega.pow(&[u64::from(n / (2 * m))]);
+    /// Swaps the coefficients (and their form) with those of `other` without
+    /// reallocating, e.g. to ping-pong between two buffers. Panics unless both
+    /// domains have the same size, so that `omega` etc. stay valid for both.
+    pub fn swap_coeffs(&mut self, other: &mut Self) {
+        self.pad();
+        other.pad();
+        assert_eq!(self.exp, other.exp);
 
-        let mut k = 0;
-        while k < n {
-            let mut w = E::Fr::one();
-            for j in 0..m {
-                let mut t = a[(k + j + m) as usize];
-                t.group_mul_assign(&w);
-                let mut tmp = a[(k + j) as usize];
-                tmp.group_sub_assign(&t);
-                a[(k + j + m) as usize] = tmp;
-                a[(k + j) as usize].group_add_assign(&t);
-                w.mul_assign(&w_m);
+        std::mem::swap(&mut self.coeffs, &mut other.coeffs);
+        std::mem::swap(&mut self.unpadded_len, &mut other.unpadded_len);
+        std::mem::swap(&mut self.form, &mut other.form);
+    }
+
+    /// Like `fft` on the CPU, but if the polynomial has so few nonzero
+    /// coefficients (e.g. a selector) that evaluating it term by term in
+    /// `O(nnz * n)` beats the `O(n log n)` FFT, it does that instead.
+    pub fn fft_sparse(&mut self, worker: &Worker)
+    where
+        G: PartialEq,
+    {
+        self.pad();
+
+        // Each term costs about two multiplications per element, the FFT
+        // `log_n`. Stop scanning once the input is known to be dense.
+        let terms = self
+            .coeffs
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| **c != G::group_zero())
+            .map(|(j, c)| (j, *c))
+            .take(self.exp as usize)
+            .collect::<Vec<_>>();
+        if terms.len() * 2 >= self.exp as usize {
+            return self
+                .fft(worker, &mut None)
+                .expect("CPU FFT without cancellation cannot fail");
+        }
+
+        let omega = self.omega;
+        worker.scope(self.coeffs.len(), |scope, chunk| {
+            for (i, v) in self.coeffs.chunks_mut(chunk).enumerate() {
+                let terms = &terms;
+                scope.spawn(move |_| {
+                    for v in v.iter_mut() {
+                        *v = G::group_zero();
+                    }
+                    for &(j, c) in terms {
+                        // v[k] += c * omega^(j * k)
+                        let w = omega.pow(&[j as u64]);
+                        let mut u = w.pow(&[(i * chunk) as u64]);
+                        for v in v.iter_mut() {
+                            let mut t = c;
+                            t.group_mul_assign(&u);
+                            v.group_add_assign(&t);
+                            u.mul_assign(&w);
+                        }
+                    }
+                });
             }
+        });
+        self.form = Form::Evaluations;
+    }
 
-            k += 2 * m;
+    /// Like `as_mut`, but errors unless the domain is in coefficient form, so
+    /// that callers can't accidentally edit evaluations as if they were
+    /// coefficients. Any padding deferred by `extend` is applied first. The
+    /// cached twiddles only depend on the domain size and stay valid.
+    pub fn coeffs_mut_in_coefficient_form(&mut self) -> Result<&mut [G], SynthesisError> {
+        if self.form != Form::Coefficients {
+            return Err(SynthesisError::InvalidDomainForm);
         }
+        self.pad();
 
-        m *= 2;
+        Ok(&mut self.coeffs)
+    }
+
+    /// Shrinks the domain to the smallest power of two that holds the
+    /// polynomial (in coefficient form), dropping zero coefficients from the
+    /// top, e.g. after it lost degree through arithmetic. The zero polynomial
+    /// ends up in a domain of size one.
+    pub fn trim_trailing_zeros(&mut self) -> Result<(), SynthesisError>
+    where
+        G: PartialEq,
+    {
+        self.check_form(Form::Coefficients)?;
+
+        let len = self.degree().map_or(1, |degree| degree + 1);
+        let exp = domain_exp::<E>(len).expect("smaller than the current domain");
+        if exp == self.exp && self.coeffs.len() == 1 << exp {
+            return Ok(());
+        }
+
+        let mut coeffs = std::mem::replace(&mut self.coeffs, Vec::new());
+        coeffs.truncate(len);
+        coeffs.resize(1 << exp, G::group_zero());
+        *self = Self::from_padded_coeffs(coeffs, exp, self.form);
+
+        Ok(())
+    }
+
+    /// Divides the polynomial (in coefficient form) by `x`, shifting the
+    /// coefficients down by one and padding the top with zero. Errors with
+    /// `SynthesisError::DivisionByZero` unless the constant term is zero, i.e.
+    /// unless zero is a root.
+    pub fn divide_by_x(&mut self) -> Result<(), SynthesisError>
+    where
+        G: PartialEq,
+    {
+        self.check_form(Form::Coefficients)?;
+        self.pad();
+
+        if self.coeffs[0] != G::group_zero() {
+            return Err(SynthesisError::DivisionByZero);
+        }
+        self.coeffs.rotate_left(1);
+
+        Ok(())
+    }
+
+    /// Splits the polynomial (in coefficient form) into the coefficients below
+    /// `mid` and the ones from `mid` on, each in a domain of its own, i.e.
+    /// `p = low + x^mid * high`. Errors with `SynthesisError::InvalidDomainSize`
+    /// unless `mid` is a power of two no larger than the domain, so that both
+    /// halves are split at a domain boundary.
+    pub fn split_at(mut self, mid: usize) -> Result<(Self, Self), SynthesisError> {
+        self.check_form(Form::Coefficients)?;
+        self.pad();
+
+        if !mid.is_power_of_two() || mid > self.coeffs.len() {
+            return Err(SynthesisError::InvalidDomainSize);
+        }
+
+        let high = self.coeffs.split_off(mid);
+        Ok((Self::from_coeffs(self.coeffs)?, Self::from_coeffs(high)?))
     }
 }
 
-fn parallel_fft<E: ScalarEngine, T: Group<E>>(
-    a: &mut [T],
-    worker: &Worker,
-    omega: &E::Fr,
-    log_n: u32,
-    log_cpus: u32,
-) {
-    assert!(log_n >= log_cpus);
+impl<E: Engine> EvaluationDomain<E, Scalar<E>> {
+    /// Builds a domain of `size` random coefficients, padded to a power of two.
+    ///
+    /// Panics if `size` exceeds the largest domain supported by the field.
+    #[cfg(feature = "test-utils")]
+    pub fn random<R: rand_core::RngCore>(size: usize, rng: &mut R) -> Self {
+        let coeffs = (0..size).map(|_| Scalar(E::Fr::random(rng))).collect();
 
-    let num_cpus = 1 << log_cpus;
-    let log_new_n = log_n - log_cpus;
-    let mut tmp = vec![vec![T::group_zero(); 1 << log_new_n]; num_cpus];
-    let new_omega = omega.pow(&[num_cpus as u64]);
+        Self::from_coeffs(coeffs).expect("domain size too large")
+    }
 
-    worker.scope(0, |scope, _| {
-        let a = &*a;
+    /// Checks that `numerator == quotient * (x^m - 1)` for the size `m` of this
+    /// domain, by schoolbook multiplication with the vanishing polynomial, as
+    /// an independent check of quotients computed by `divide_by_z_on_coset`.
+    /// Trailing zero coefficients are ignored.
+    #[cfg(feature = "test-utils")]
+    pub fn verify_quotient(
+        &self,
+        numerator: &[E::Fr],
+        quotient: &[E::Fr],
+        worker: &Worker,
+    ) -> bool {
+        let product = naive_mul::<E>(quotient, &self.vanishing_poly_coeffs(), worker);
+        let trim = |poly: &[E::Fr]| {
+            let len = poly
+                .iter()
+                .rposition(|c| !c.is_zero())
+                .map_or(0, |degree| degree + 1);
+            poly[..len].to_vec()
+        };
 
-        for (j, tmp) in tmp.iter_mut().enumerate() {
-            scope.spawn(move |_scope| {
-                // Shuffle into a sub-FFT
-                let omega_j = omega.pow(&[j as u64]);
-                let omega_step = omega.pow(&[(j as u64) << log_new_n]);
+        trim(numerator) == trim(&product)
+    }
 
-                let mut elt = E::Fr::one();
-                for (i, tmp) in tmp.iter_mut().enumerate() {
-                    for s in 0..num_cpus {
-                        let idx = (i + (s << log_new_n)) % (1 << log_n);
-                        let mut t = a[idx];
-                        t.group_mul_assign(&elt);
-                        tmp.group_add_assign(&t);
-                        elt.mul_assign(&omega_step);
+    /// Returns the sum of all coefficients, i.e. the polynomial evaluated at 1.
+    pub fn sum_of_coeffs(&self, worker: &Worker) -> E::Fr {
+        let chunk = worker.get_chunk_size(self.coeffs.len());
+        let mut partials = vec![Scalar::<E>::group_zero(); self.coeffs.chunks(chunk).len()];
+
+        worker.scope(0, |scope, _| {
+            for (sum, v) in partials.iter_mut().zip(self.coeffs.chunks(chunk)) {
+                scope.spawn(move |_| {
+                    for v in v {
+                        sum.group_add_assign(v);
                     }
-                    elt.mul_assign(&omega_j);
-                }
+                });
+            }
+        });
 
-                // Perform sub-FFT
-                serial_fft(tmp, &new_omega, log_new_n);
-            });
+        partials.iter().fold(E::Fr::zero(), |mut acc, sum| {
+            acc.add_assign(&sum.0);
+            acc
+        })
+    }
+
+    /// Returns the polynomial (in coefficient form) evaluated at `omega^k`, i.e.
+    /// element `k` of its `fft`, in `O(n)` rather than transforming the whole
+    /// domain.
+    pub fn eval_at_root(&self, k: usize, worker: &Worker) -> Result<E::Fr, SynthesisError> {
+        self.check_form(Form::Coefficients)?;
+        if let Some(padded) = self.padded_copy() {
+            return padded.eval_at_root(k, worker);
         }
-    });
 
-    // TODO: does this hurt or help?
-    worker.scope(a.len(), |scope, chunk| {
-        let tmp = &tmp;
+        let point = self.omega.pow(&[k as u64]);
+        let chunk = worker.get_chunk_size(self.coeffs.len());
+        let mut partials = vec![E::Fr::zero(); self.coeffs.chunks(chunk).len()];
+
+        worker.scope(0, |scope, _| {
+            for (i, (sum, v)) in partials
+                .iter_mut()
+                .zip(self.coeffs.chunks(chunk))
+                .enumerate()
+            {
+                scope.spawn(move |_| {
+                    *sum = horner::<E>(scalars_as_fr(v), &point);
+                    sum.mul_assign(&point.pow(&[(i * chunk) as u64]));
+                });
+            }
+        });
+
+        Ok(partials.iter().fold(E::Fr::zero(), |mut acc, sum| {
+            acc.add_assign(sum);
+            acc
+        }))
+    }
+
+    /// Returns `sum_i self[i] * other[i]`, the dot product of the coefficients.
+    pub fn inner_product(&self, other: &EvaluationDomain<E, Scalar<E>>, worker: &Worker) -> E::Fr {
+        if let Some(padded) = self.padded_copy() {
+            return padded.inner_product(other, worker);
+        }
+        if let Some(other) = other.padded_copy() {
+            return self.inner_product(&other, worker);
+        }
+        assert_same_len!(self.coeffs.len(), other.coeffs.len());
+
+        let chunk = worker.get_chunk_size(self.coeffs.len());
+        let mut partials = vec![E::Fr::zero(); self.coeffs.chunks(chunk).len()];
+
+        worker.scope(0, |scope, _| {
+            for ((sum, a), b) in partials
+                .iter_mut()
+                .zip(self.coeffs.chunks(chunk))
+                .zip(other.coeffs.chunks(chunk))
+            {
+                scope.spawn(move |_| {
+                    for (a, b) in a.iter().zip(b.iter()) {
+                        let mut tmp = a.0;
+                        tmp.mul_assign(&b.0);
+                        sum.add_assign(&tmp);
+                    }
+                });
+            }
+        });
+
+        partials.iter().fold(E::Fr::zero(), |mut acc, sum| {
+            acc.add_assign(sum);
+            acc
+        })
+    }
+
+    /// Returns the product of the polynomials `a` and `b` (in coefficient form)
+    /// in a fresh domain sized to fit the product, leaving the inputs untouched.
+    pub fn mul(
+        a: &Self,
+        b: &Self,
+        worker: &Worker,
+        kern: &mut Option<gpu::LockedFFTKernel<E>>,
+    ) -> Result<Self, SynthesisError> {
+        let (deg_a, deg_b) = match (a.degree(), b.degree()) {
+            (Some(deg_a), Some(deg_b)) => (deg_a, deg_b),
+            _ => return Self::from_coeffs(vec![Scalar(E::Fr::zero())]),
+        };
+        if deg_a == 0 {
+            return Self::scaled_by_constant(b.coeffs[..=deg_b].to_vec(), a.coeffs[0].0, worker);
+        }
+        if deg_b == 0 {
+            return Self::scaled_by_constant(a.coeffs[..=deg_a].to_vec(), b.coeffs[0].0, worker);
+        }
+        let len = deg_a + deg_b + 1;
+
+        let mut a = Self::from_coeffs_padded_to(a.coeffs[..=deg_a].to_vec(), len)?;
+        let mut b = Self::from_coeffs_padded_to(b.coeffs[..=deg_b].to_vec(), len)?;
+
+        a.fft(worker, kern)?;
+        b.fft(worker, kern)?;
+        a.mul_assign(worker, &b);
+        a.ifft(worker, kern)?;
+
+        Ok(a)
+    }
+
+    /// Returns the monic polynomial `(x - r_1)·…·(x - r_k)` with the given
+    /// roots, e.g. the vanishing polynomial of a set of points other than the
+    /// roots of unity. The linear factors are multiplied up a balanced product
+    /// tree with `mul`, which takes `O(k log² k)`.
+    pub fn from_roots(
+        roots: &[E::Fr],
+        worker: &Worker,
+        kern: &mut Option<gpu::LockedFFTKernel<E>>,
+    ) -> Result<Self, SynthesisError> {
+        let mut level = roots
+            .iter()
+            .map(|r| {
+                let mut neg = *r;
+                neg.negate();
+                Self::from_coeffs(vec![Scalar(neg), Scalar(E::Fr::one())])
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if level.is_empty() {
+            return Self::from_coeffs(vec![Scalar(E::Fr::one())]);
+        }
+
+        while level.len() > 1 {
+            let mut next = Vec::with_capacity((level.len() + 1) / 2);
+            let mut factors = level.into_iter();
+            while let Some(a) = factors.next() {
+                next.push(match factors.next() {
+                    Some(b) => Self::mul(&a, &b, worker, kern)?,
+                    None => a,
+                });
+            }
+            level = next;
+        }
+
+        Ok(level.pop().unwrap())
+    }
+
+    /// Multiplies the polynomial (in coefficient form) by `other`, resizing the
+    /// domain to fit the product. Unlike `mul_assign`, neither operand needs to
+    /// be padded or transformed beforehand. On error `self` is left untouched.
+    pub fn checked_mul_assign(
+        &mut self,
+        worker: &Worker,
+        kern: &mut Option<gpu::LockedFFTKernel<E>>,
+        other: &[E::Fr],
+    ) -> Result<(), SynthesisError> {
+        let deg_other = other.iter().rposition(|c| !c.is_zero());
+        let (deg_self, deg_other) = match (self.degree(), deg_other) {
+            (Some(deg_self), Some(deg_other)) => (deg_self, deg_other),
+            _ => {
+                *self = Self::from_coeffs(vec![Scalar(E::Fr::zero())])?;
+                return Ok(());
+            }
+        };
+        if deg_self == 0 || deg_other == 0 {
+            let (coeffs, c) = if deg_other == 0 {
+                (self.coeffs[..=deg_self].to_vec(), other[0])
+            } else {
+                let coeffs = other[..=deg_other].iter().map(|c| Scalar(*c)).collect();
+                (coeffs, self.coeffs[0].0)
+            };
+            *self = Self::scaled_by_constant(coeffs, c, worker)?;
+            return Ok(());
+        }
+        let len = deg_self + deg_other + 1;
+        domain_exp::<E>(len)?;
+
+        // Work on a copy, so that `self` is only replaced once nothing can fail
+        let mut product = Self::from_coeffs_padded_to(self.coeffs[..=deg_self].to_vec(), len)?;
+
+        let mut other_coeffs = other[..=deg_other]
+            .iter()
+            .map(|c| Scalar(*c))
+            .collect::<Vec<_>>();
+        other_coeffs.resize(len, Scalar(E::Fr::zero()));
+        let mut other = Self::from_coeffs(other_coeffs)?;
+
+        product.fft(worker, kern)?;
+        other.fft(worker, kern)?;
+        product.mul_assign(worker, &other);
+        product.ifft(worker, kern)?;
+        *self = product;
+
+        Ok(())
+    }
+
+    /// Multiplies `coeffs` by the constant polynomial `c` without any
+    /// transforms, for the products of `mul` and `checked_mul_assign` with a
+    /// constant operand.
+    fn scaled_by_constant(
+        coeffs: Vec<Scalar<E>>,
+        c: E::Fr,
+        worker: &Worker,
+    ) -> Result<Self, SynthesisError> {
+        let mut product = Self::from_coeffs(coeffs)?;
+        product.map_in_place(worker, |v| v.0.mul_assign(&c));
+        Ok(product)
+    }
+
+    /// Returns the coefficients as field elements, without copying.
+    pub fn as_fr(&self) -> &[E::Fr] {
+        scalars_as_fr(&self.coeffs)
+    }
+
+    /// Computes the Groth16 quotient `h = (a·b - c) / z` and returns its
+    /// coefficients. `a`, `b` and `c` hold the evaluations of the constraint
+    /// polynomials over the domain, so `a·b - c` is divisible by `z`; the
+    /// division itself is carried out over a coset, where `z` has no roots.
+    /// The product has degree `2n - 2` and the quotient `n - 2`, so the
+    /// last coefficient, which is always zero, is dropped.
+    pub fn groth16_quotient(
+        mut a: Self,
+        mut b: Self,
+        mut c: Self,
+        worker: &Worker,
+        kern: &mut Option<gpu::LockedFFTKernel<E>>,
+    ) -> gpu::GPUResult<Vec<E::Fr>> {
+        a.ifft(worker, kern)?;
+        a.coset_fft(worker, kern)?;
+        b.ifft(worker, kern)?;
+        b.coset_fft(worker, kern)?;
+        c.ifft(worker, kern)?;
+        c.coset_fft(worker, kern)?;
+
+        a.mul_assign(worker, &b);
+        drop(b);
+        a.sub_assign(worker, &c);
+        drop(c);
+        a.divide_by_z_on_coset(worker);
+        a.icoset_fft(worker, kern)?;
+
+        let mut h = a.into_coeffs();
+        h.pop();
+        Ok(h.into_iter().map(|s| s.0).collect())
+    }
+
+    /// Evaluates the polynomial (in coefficient form) at `point`.
+    pub fn evaluate(&self, point: &E::Fr) -> E::Fr {
+        horner::<E>(self.as_fr(), point)
+    }
+
+    /// Evaluates the polynomial (in coefficient form) at each of `points`,
+    /// using a subproduct tree: the polynomial is reduced modulo
+    /// `prod (x - p)` over ever smaller subsets of the points, which takes
+    /// O(n log^2 n) operations instead of the O(n^2) of repeated `evaluate`.
+    pub fn evaluate_multi(
+        &self,
+        points: &[E::Fr],
+        worker: &Worker,
+    ) -> Result<Vec<E::Fr>, SynthesisError> {
+        if points.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let coeffs = &self.as_fr()[..self.degree().map_or(0, |degree| degree + 1)];
+
+        // levels[0] holds `prod (x - p)` for blocks of `MULTIPOINT_LEAF_SIZE`
+        // points, each further level the products of pairs of the level below.
+        let mut levels = vec![points
+            .chunks(MULTIPOINT_LEAF_SIZE)
+            .map(|block| {
+                block.iter().fold(vec![E::Fr::one()], |acc, p| {
+                    let mut minus_p = *p;
+                    minus_p.negate();
+                    naive_poly_mul::<E>(&acc, &[minus_p, E::Fr::one()])
+                })
+            })
+            .collect::<Vec<_>>()];
+        while levels[levels.len() - 1].len() > 1 {
+            let next = levels[levels.len() - 1]
+                .chunks(2)
+                .map(|pair| match pair {
+                    [a, b] => poly_mul::<E>(a, b, worker),
+                    _ => Ok(pair[0].clone()),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            levels.push(next);
+        }
+
+        // Reduce down the tree, each node modulo its own product.
+        let mut rems = vec![coeffs.to_vec()];
+        for level in levels.iter().rev() {
+            rems = level
+                .iter()
+                .enumerate()
+                .map(|(i, node)| poly_rem::<E>(&rems[i / 2], node, worker))
+                .collect::<Result<Vec<_>, _>>()?;
+        }
+
+        Ok(points
+            .chunks(MULTIPOINT_LEAF_SIZE)
+            .zip(rems.iter())
+            .flat_map(|(block, rem)| block.iter().map(move |p| horner::<E>(rem, p)))
+            .collect())
+    }
+
+    /// Writes the evaluations `coset_fft` would compute into `out`, leaving
+    /// `self` untouched and, unless `extend` deferred its padding, without
+    /// allocating a copy of the coefficients.
+    #[must_use = "`out` only holds the coset evaluations if the FFT succeeded"]
+    pub fn coset_fft_into(
+        &self,
+        out: &mut [E::Fr],
+        worker: &Worker,
+        kern: &mut Option<gpu::LockedFFTKernel<E>>,
+    ) -> gpu::GPUResult<()> {
+        if let Some(padded) = self.padded_copy() {
+            return padded.coset_fft_into(out, worker, kern);
+        }
+        assert_eq!(out.len(), self.coeffs.len());
+
+        out.copy_from_slice(self.as_fr());
+        let out = fr_as_scalars_mut::<E>(out);
+        distribute_powers(out, worker, E::Fr::multiplicative_generator());
+        best_fft(
+            kern,
+            out,
+            worker,
+            &self.omega,
+            self.exp,
+            None,
+            CpuFftOptions::default(),
+        )?;
+        Ok(())
+    }
+
+    /// Returns the evaluations at `omega^i` for each `i` in `output_indices`,
+    /// i.e. the given entries of what `fft` would compute. Few outputs are
+    /// evaluated directly in `O(m)` each; once that would cost more than a
+    /// full `O(m log m)` transform, this falls back to `fft` on a copy.
+    pub fn partial_fft(&self, output_indices: &[usize], worker: &Worker) -> Vec<E::Fr> {
+        if let Some(padded) = self.padded_copy() {
+            return padded.partial_fft(output_indices, worker);
+        }
+
+        let m = self.coeffs.len();
+        assert!(output_indices.iter().all(|i| *i < m));
+
+        if output_indices.len() > self.exp as usize {
+            let mut evals = self.coeffs.clone();
+            cpu_fft(
+                &mut evals,
+                worker,
+                &self.omega,
+                self.exp,
+                None,
+                CpuFftOptions::default(),
+            )
+            .expect("FFT without cancellation cannot fail");
+            return output_indices.iter().map(|i| evals[*i].0).collect();
+        }
+
+        let mut res = vec![E::Fr::zero(); output_indices.len()];
+        worker.scope(output_indices.len(), |scope, chunk| {
+            for (res, indices) in res.chunks_mut(chunk).zip(output_indices.chunks(chunk)) {
+                scope.spawn(move |_| {
+                    for (res, i) in res.iter_mut().zip(indices.iter()) {
+                        *res = self.evaluate(&self.omega.pow(&[*i as u64]));
+                    }
+                });
+            }
+        });
+        res
+    }
+
+    /// Updates the evaluations (as computed by `fft`) for adding `delta` to
+    /// the coefficient at `coeff_index`. The transform is linear, so this adds
+    /// `delta * omega^(coeff_index * j)` to the `j`-th evaluation, in O(n)
+    /// instead of the O(n log n) of a fresh `fft`.
+    pub fn update_evaluation(&mut self, coeff_index: usize, delta: E::Fr, worker: &Worker) {
+        self.pad();
+        assert!(coeff_index < self.coeffs.len());
+
+        let g = self.omega.pow(&[coeff_index as u64]);
+        worker.scope(self.coeffs.len(), |scope, chunk| {
+            for (i, v) in self.coeffs.chunks_mut(chunk).enumerate() {
+                scope.spawn(move |_| {
+                    let mut u = g.pow(&[(i * chunk) as u64]);
+                    u.mul_assign(&delta);
+                    for v in v.iter_mut() {
+                        v.0.add_assign(&u);
+                        u.mul_assign(&g);
+                    }
+                });
+            }
+        });
+    }
+
+    /// Returns the evaluations cyclically shifted by `k` positions, so that the
+    /// result at `omega^i` equals `self` at `omega^(i + k)`, e.g. the shifted
+    /// polynomial of a permutation argument. A negative `k` shifts the other
+    /// way. Only meaningful in evaluation form.
+    pub fn rotate(
+        &self,
+        k: isize,
+        worker: &Worker,
+    ) -> Result<EvaluationDomain<E, Scalar<E>>, SynthesisError> {
+        self.check_form(Form::Evaluations)?;
+
+        let n = self.coeffs.len();
+        let shift = k.rem_euclid(n as isize) as usize;
+        let mut coeffs = vec![Scalar(E::Fr::zero()); n];
+        worker.scope(n, |scope, chunk| {
+            let src = &self.coeffs;
+            for (i, dst) in coeffs.chunks_mut(chunk).enumerate() {
+                scope.spawn(move |_| {
+                    let start = i * chunk + shift;
+                    for (j, v) in dst.iter_mut().enumerate() {
+                        *v = src[(start + j) % n];
+                    }
+                });
+            }
+        });
+
+        Ok(Self::from_padded_coeffs(coeffs, self.exp, self.form))
+    }
+
+    /// Feeds the canonical little-endian encoding of each coefficient, in
+    /// order, to `f`, e.g. to absorb the polynomial into a Fiat-Shamir
+    /// transcript without serializing it into one buffer first. The zero
+    /// padding up to the domain size is always included (even if an `extend`
+    /// hasn't applied it yet), so the output only depends on the polynomial
+    /// and the domain size.
+    pub fn for_each_coeff_bytes<F: FnMut(&[u8])>(&self, mut f: F) {
+        use ff::PrimeFieldRepr;
+
+        let padded_len = self.coeffs.len().next_power_of_two();
+        let zero = Scalar(E::Fr::zero());
+        let mut buf = Vec::with_capacity(std::mem::size_of::<<E::Fr as PrimeField>::Repr>());
+        for coeff in self
+            .coeffs
+            .iter()
+            .chain(std::iter::repeat(&zero))
+            .take(padded_len)
+        {
+            buf.clear();
+            coeff
+                .0
+                .into_repr()
+                .write_le(&mut buf)
+                .expect("writing to a Vec cannot fail");
+            f(&buf);
+        }
+    }
+
+    /// Returns a fast, non-cryptographic hash (64-bit FNV-1a) of the bytes
+    /// `for_each_coeff_bytes` produces, e.g. to compare large domains before
+    /// falling back to comparing their coefficients. Equal polynomials in
+    /// domains of equal size always hash equally.
+    pub fn coeffs_hash(&self) -> u64 {
+        const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+        let mut hash = FNV_OFFSET_BASIS;
+        self.for_each_coeff_bytes(|bytes| {
+            for &b in bytes {
+                hash ^= u64::from(b);
+                hash = hash.wrapping_mul(FNV_PRIME);
+            }
+        });
+        hash
+    }
+
+    /// Returns the bytes `for_each_coeff_bytes` feeds to its closure, as one
+    /// buffer. Chunks of coefficients are serialized in parallel, each into its
+    /// own part of the buffer.
+    pub fn coeffs_to_bytes_par(&self, worker: &Worker) -> Vec<u8> {
+        use ff::PrimeFieldRepr;
+
+        let repr_len = E::Fr::zero().into_repr().as_ref().len() * 8;
+        let padded_len = self.coeffs.len().next_power_of_two();
+        let mut bytes = vec![0u8; padded_len * repr_len];
+
+        // The zero padding is already encoded by the zeroed buffer
+        let used = &mut bytes[..self.coeffs.len() * repr_len];
+        worker.scope(self.coeffs.len(), |scope, chunk| {
+            for (coeffs, mut out) in self
+                .coeffs
+                .chunks(chunk)
+                .zip(used.chunks_mut(chunk * repr_len))
+            {
+                scope.spawn(move |_| {
+                    for coeff in coeffs {
+                        coeff
+                            .0
+                            .into_repr()
+                            .write_le(&mut out)
+                            .expect("the buffer fits every coefficient");
+                    }
+                });
+            }
+        });
+
+        bytes
+    }
+
+    /// Commits to the polynomial with the structured reference string `srs`,
+    /// i.e. computes `sum_i coeffs[i] * srs[i]`. The multi-scalar
+    /// multiplication runs on the GPU if `kern` is given, and falls back to a
+    /// parallel Pippenger on `worker` otherwise. The SRS may be longer than the
+    /// polynomial but must not contain the identity.
+    pub fn commit_gpu<G>(
+        &self,
+        srs: &EvaluationDomain<E, Point<G>>,
+        worker: &Worker,
+        kern: &mut Option<gpu::LockedMultiexpKernel<E>>,
+    ) -> Result<G, SynthesisError>
+    where
+        G: CurveProjective<Engine = E, Scalar = E::Fr>,
+        G::Affine: CurveAffine<Engine = E>,
+    {
+        self.check_form(Form::Coefficients)?;
+        if self.coeffs.len() > srs.coeffs.len() {
+            return Err(SynthesisError::PolynomialDegreeTooLarge);
+        }
+
+        let mut bases = srs.coeffs[..self.coeffs.len()]
+            .iter()
+            .map(|p| p.0)
+            .collect::<Vec<_>>();
+        G::batch_normalization(&mut bases);
+        let bases = bases.iter().map(|p| p.into_affine()).collect::<Vec<_>>();
+        let exponents = self
+            .coeffs
+            .iter()
+            .map(|c| c.0.into_repr())
+            .collect::<Vec<_>>();
+
+        multiexp(
+            worker,
+            (Arc::new(bases), 0),
+            FullDensity,
+            Arc::new(exponents),
+            kern,
+        )
+        .wait()
+    }
+
+    /// Reduces the polynomial (in coefficient form) modulo `x^k - 1`, by summing
+    /// the coefficients at indices congruent modulo `k`. Errors with
+    /// `SynthesisError::InvalidDomainSize` unless `k` is a power of two
+    /// dividing the size of the domain.
+    pub fn reduce_mod_cyclic(&self, k: usize) -> Result<Vec<E::Fr>, SynthesisError> {
+        self.check_form(Form::Coefficients)?;
+        if let Some(padded) = self.padded_copy() {
+            return padded.reduce_mod_cyclic(k);
+        }
+
+        if !k.is_power_of_two() || k > 1 << self.exp {
+            return Err(SynthesisError::InvalidDomainSize);
+        }
+
+        let mut folded = scalars_as_fr(&self.coeffs[..k]).to_vec();
+        for chunk in self.coeffs[k..].chunks(k) {
+            for (f, c) in folded.iter_mut().zip(chunk) {
+                f.add_assign(&c.0);
+            }
+        }
+
+        Ok(folded)
+    }
+
+    /// Evaluates the polynomial (in evaluation form) at `z` via the barycentric
+    /// formula `(z^m - 1)/m * sum_i f_i * omega^i / (z - omega^i)`, without
+    /// going back to coefficient form. Errors with
+    /// `SynthesisError::DivisionByZero` if `z` is a root of the domain.
+    pub fn barycentric_eval(&self, z: &E::Fr, worker: &Worker) -> Result<E::Fr, SynthesisError> {
+        self.check_form(Form::Evaluations)?;
+
+        let mut scale = self.z(z);
+        if scale.is_zero() {
+            return Err(SynthesisError::DivisionByZero);
+        }
+        scale.mul_assign(&self.minv);
+
+        let roots = self.roots(worker);
+        let mut denominators = roots
+            .iter()
+            .map(|root| {
+                let mut d = *z;
+                d.sub_assign(root);
+                d
+            })
+            .collect::<Vec<_>>();
+        batch_inversion(&mut denominators, worker);
+
+        let chunk = worker.get_chunk_size(self.coeffs.len());
+        let mut partials = vec![E::Fr::zero(); self.coeffs.chunks(chunk).len()];
+        worker.scope(0, |scope, _| {
+            for (((sum, f), root), d) in partials
+                .iter_mut()
+                .zip(self.coeffs.chunks(chunk))
+                .zip(roots.chunks(chunk))
+                .zip(denominators.chunks(chunk))
+            {
+                scope.spawn(move |_| {
+                    for ((f, root), d) in f.iter().zip(root).zip(d) {
+                        let mut term = f.0;
+                        term.mul_assign(root);
+                        term.mul_assign(d);
+                        sum.add_assign(&term);
+                    }
+                });
+            }
+        });
+
+        let mut result = partials.iter().fold(E::Fr::zero(), |mut acc, sum| {
+            acc.add_assign(sum);
+            acc
+        });
+        result.mul_assign(&scale);
+
+        Ok(result)
+    }
+
+    /// Returns the running products `[c_0, c_0·c_1, c_0·c_1·c_2, ...]` of the
+    /// coefficients (in whatever form they are), e.g. for the grand product of
+    /// a permutation argument.
+    pub fn prefix_product(&self, worker: &Worker) -> Vec<E::Fr> {
+        if let Some(padded) = self.padded_copy() {
+            return padded.prefix_product(worker);
+        }
+
+        prefix_product(self.as_fr(), worker)
+    }
+}
+
+pub trait Group<E: ScalarEngine>: Sized + Copy + Clone + Send + Sync {
+    fn group_zero() -> Self;
+    fn group_mul_assign(&mut self, by: &E::Fr);
+    fn group_add_assign(&mut self, other: &Self);
+    fn group_sub_assign(&mut self, other: &Self);
+}
+
+pub struct Point<G: CurveProjective>(pub G);
+
+impl<G: CurveProjective> PartialEq for Point<G> {
+    fn eq(&self, other: &Point<G>) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<G: CurveProjective> Copy for Point<G> {}
+
+impl<G: CurveProjective> Clone for Point<G> {
+    fn clone(&self) -> Point<G> {
+        *self
+    }
+}
+
+impl<G: CurveProjective> Group<G::Engine> for Point<G> {
+    fn group_zero() -> Self {
+        Point(G::zero())
+    }
+    fn group_mul_assign(&mut self, by: &G::Scalar) {
+        self.0.mul_assign(by.into_repr());
+    }
+    fn group_add_assign(&mut self, other: &Self) {
+        self.0.add_assign(&other.0);
+    }
+    fn group_sub_assign(&mut self, other: &Self) {
+        self.0.sub_assign(&other.0);
+    }
+}
+
+/// A point stored in affine form, which takes about two thirds of the memory
+/// of a `Point`. Every operation converts to projective form and back, which
+/// costs an inversion, so this suits large domains of points which are stored
+/// rather than transformed, e.g. commitment keys.
+pub struct AffinePoint<G: CurveAffine>(pub G);
+
+impl<G: CurveAffine> PartialEq for AffinePoint<G> {
+    fn eq(&self, other: &AffinePoint<G>) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<G: CurveAffine> Copy for AffinePoint<G> {}
+
+impl<G: CurveAffine> Clone for AffinePoint<G> {
+    fn clone(&self) -> AffinePoint<G> {
+        *self
+    }
+}
+
+impl<G: CurveAffine> Group<G::Engine> for AffinePoint<G> {
+    fn group_zero() -> Self {
+        AffinePoint(G::zero())
+    }
+    fn group_mul_assign(&mut self, by: &G::Scalar) {
+        self.0 = self.0.mul(by.into_repr()).into_affine();
+    }
+    fn group_add_assign(&mut self, other: &Self) {
+        let mut sum = self.0.into_projective();
+        sum.add_assign_mixed(&other.0);
+        self.0 = sum.into_affine();
+    }
+    fn group_sub_assign(&mut self, other: &Self) {
+        let mut neg = other.0;
+        neg.negate();
+        self.group_add_assign(&AffinePoint(neg));
+    }
+}
+
+#[repr(transparent)]
+pub struct Scalar<E: ScalarEngine>(pub E::Fr);
+
+impl<E: ScalarEngine> PartialEq for Scalar<E> {
+    fn eq(&self, other: &Scalar<E>) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<E: ScalarEngine> Copy for Scalar<E> {}
+
+impl<E: ScalarEngine> Clone for Scalar<E> {
+    fn clone(&self) -> Scalar<E> {
+        *self
+    }
+}
+
+/// Views scalars as field elements, without copying.
+fn scalars_as_fr<E: ScalarEngine>(a: &[Scalar<E>]) -> &[E::Fr] {
+    // `Scalar<E>` is `repr(transparent)` over `E::Fr`, so both slices have the
+    // same layout.
+    unsafe { std::slice::from_raw_parts(a.as_ptr() as *const E::Fr, a.len()) }
+}
+
+/// Views field elements as scalars, without copying.
+fn fr_as_scalars_mut<E: ScalarEngine>(a: &mut [E::Fr]) -> &mut [Scalar<E>] {
+    // See `scalars_as_fr`.
+    unsafe { std::slice::from_raw_parts_mut(a.as_mut_ptr() as *mut Scalar<E>, a.len()) }
+}
+
+/// Views scalars as field elements, without copying.
+fn scalars_as_fr_mut<E: ScalarEngine>(a: &mut [Scalar<E>]) -> &mut [E::Fr] {
+    // See `scalars_as_fr`.
+    unsafe { std::slice::from_raw_parts_mut(a.as_mut_ptr() as *mut E::Fr, a.len()) }
+}
+
+impl<E: ScalarEngine> Group<E> for Scalar<E> {
+    fn group_zero() -> Self {
+        Scalar(E::Fr::zero())
+    }
+    fn group_mul_assign(&mut self, by: &E::Fr) {
+        self.0.mul_assign(by);
+    }
+    fn group_add_assign(&mut self, other: &Self) {
+        self.0.add_assign(&other.0);
+    }
+    fn group_sub_assign(&mut self, other: &Self) {
+        self.0.sub_assign(&other.0);
+    }
+}
+
+/// Like `coeffs.resize(len, zero)`, but returns `SynthesisError::OutOfMemory`
+/// if growing the vector fails, as `Vec::try_reserve` isn't stable yet.
+fn try_resize<T: Copy>(mut coeffs: Vec<T>, len: usize, zero: T) -> Result<Vec<T>, SynthesisError> {
+    if coeffs.capacity() < len && std::mem::size_of::<T>() != 0 {
+        let layout =
+            std::alloc::Layout::array::<T>(len).map_err(|_| SynthesisError::OutOfMemory)?;
+        let ptr = unsafe { std::alloc::alloc(layout) } as *mut T;
+        if ptr.is_null() {
+            return Err(SynthesisError::OutOfMemory);
+        }
+        // Safe, as `ptr` was allocated by the global allocator with the layout
+        // of a `Vec<T>` with capacity `len`.
+        let mut grown = unsafe { Vec::from_raw_parts(ptr, 0, len) };
+        grown.extend_from_slice(&coeffs);
+        coeffs = grown;
+    }
+    coeffs.resize(len, zero);
+
+    Ok(coeffs)
+}
+
+/// Returns the `exp` of the domain `from_coeffs` chooses for a polynomial of
+/// degree `max_degree`, e.g. to size the kernel passed to `create_fft_kernel`.
+pub fn domain_exp_for_degree<E: ScalarEngine>(max_degree: usize) -> Result<u32, SynthesisError> {
+    let len = max_degree
+        .checked_add(1)
+        .ok_or(SynthesisError::PolynomialDegreeTooLarge)?;
+    domain_exp::<E>(len)
+}
+
+/// Returns the size `2^exp` of a domain as a field element, by doubling.
+fn domain_size<F: Field>(exp: u32) -> F {
+    let mut m = F::one();
+    for _ in 0..exp {
+        m.double();
+    }
+    m
+}
+
+/// Returns the `exp` of the smallest radix-2 domain holding `len` coefficients.
+fn domain_exp<E: ScalarEngine>(len: usize) -> Result<u32, SynthesisError> {
+    let mut m = 1;
+    let mut exp = 0;
+    while m < len {
+        m *= 2;
+        exp += 1;
+
+        // The pairing-friendly curve may not be able to support
+        // large enough (radix2) evaluation domains.
+        if exp >= E::Fr::S {
+            return Err(SynthesisError::PolynomialDegreeTooLarge);
+        }
+    }
+
+    Ok(exp)
+}
+
+/// A multiplicative subgroup of order `2^twos * 3^threes`, used to build
+/// [`MixedRadixDomain`]s in fields whose two-adicity `S` is too small for the
+/// required radix-2 domains.
+#[derive(Clone, Copy, Debug)]
+pub struct SmoothSubgroup<F: PrimeField> {
+    /// A primitive root of unity of order `2^twos * 3^threes`.
+    pub root: F,
+    pub twos: u32,
+    pub threes: u32,
+}
+
+/// An evaluation domain of size `2^a * 3^b`, transformed with a mixed-radix FFT.
+pub struct MixedRadixDomain<E: ScalarEngine, G: Group<E>> {
+    coeffs: Vec<G>,
+    radices: Vec<u32>,
+    omega: E::Fr,
+    omegainv: E::Fr,
+    minv: E::Fr,
+}
+
+impl<E: ScalarEngine, G: Group<E>> AsRef<[G]> for MixedRadixDomain<E, G> {
+    fn as_ref(&self) -> &[G] {
+        &self.coeffs
+    }
+}
+
+impl<E: ScalarEngine, G: Group<E>> AsMut<[G]> for MixedRadixDomain<E, G> {
+    fn as_mut(&mut self) -> &mut [G] {
+        &mut self.coeffs
+    }
+}
+
+impl<E: Engine, G: Group<E>> MixedRadixDomain<E, G> {
+    pub fn into_coeffs(self) -> Vec<G> {
+        self.coeffs
+    }
+
+    /// Pads `coeffs` to the smallest `2^a * 3^b` with `a <= subgroup.twos` and
+    /// `b <= subgroup.threes` and sets up the domain over the matching subgroup.
+    pub fn from_coeffs_smooth(
+        mut coeffs: Vec<G>,
+        subgroup: &SmoothSubgroup<E::Fr>,
+    ) -> Result<MixedRadixDomain<E, G>, SynthesisError> {
+        // Find the smallest 2^twos * 3^threes covering the coefficients
+        let mut best: Option<(u64, u32, u32)> = None;
+        let mut pow3 = 1u64;
+        for threes in 0..=subgroup.threes {
+            let mut m = pow3;
+            let mut twos = 0;
+            while m < coeffs.len() as u64 && twos < subgroup.twos {
+                m *= 2;
+                twos += 1;
+            }
+            if m >= coeffs.len() as u64 && best.map_or(true, |(b, _, _)| m < b) {
+                best = Some((m, twos, threes));
+            }
+
+            pow3 = match pow3.checked_mul(3) {
+                Some(pow3) => pow3,
+                None => break,
+            };
+        }
+        let (m, twos, threes) = best.ok_or(SynthesisError::PolynomialDegreeTooLarge)?;
+
+        // Compute omega, the primitive m-th root of unity
+        let mut omega = subgroup.root;
+        for _ in twos..subgroup.twos {
+            omega.square();
+        }
+        for _ in threes..subgroup.threes {
+            omega = omega.pow(&[3]);
+        }
+
+        // Extend the coeffs vector with zeroes if necessary
+        coeffs.resize(m as usize, G::group_zero());
+
+        let mut radices = vec![3; threes as usize];
+        radices.extend(vec![2; twos as usize]);
+
+        // Compute m as a field element, by doubling and tripling
+        let mut m = domain_size::<E::Fr>(twos);
+        for _ in 0..threes {
+            let once = m;
+            m.double();
+            m.add_assign(&once);
+        }
+
+        Ok(MixedRadixDomain {
+            coeffs,
+            radices,
+            omega,
+            omegainv: omega.inverse().unwrap(),
+            minv: m.inverse().unwrap(),
+        })
+    }
+
+    pub fn fft(&mut self, worker: &Worker) {
+        mixed_radix_fft(&mut self.coeffs, worker, &self.omega, &self.radices);
+    }
+
+    pub fn ifft(&mut self, worker: &Worker) {
+        mixed_radix_fft(&mut self.coeffs, worker, &self.omegainv, &self.radices);
+
+        let minv = self.minv;
+        map_coeffs_in_place(&mut self.coeffs, worker, |v| v.group_mul_assign(&minv));
+    }
+}
+
+/// Runs `f` on every element of `coeffs`, in parallel.
+fn map_coeffs_in_place<G: Send, F: Fn(&mut G) + Sync>(coeffs: &mut [G], worker: &Worker, f: F) {
+    worker.in_place_scope(coeffs, |_, v| {
+        for v in v {
+            f(v);
+        }
+    });
+}
+
+/// Multiplies `coeffs[i]` by `g^i`.
+fn distribute_powers<E: ScalarEngine, G: Group<E>>(coeffs: &mut [G], worker: &Worker, g: E::Fr) {
+    tuned_scope(
+        worker,
+        DomainOp::DistributePowers,
+        coeffs.len(),
+        |scope, chunk| {
+            for (i, v) in coeffs.chunks_mut(chunk).enumerate() {
+                scope.spawn(move |_| {
+                    let mut u = g.pow(&[(i * chunk) as u64]);
+                    for v in v.iter_mut() {
+                        v.group_mul_assign(&u);
+                        u.mul_assign(&g);
+                    }
+                });
+            }
+        },
+    );
+}
+
+/// A copy-on-write [`EvaluationDomain`], created by
+/// [`EvaluationDomain::from_coeffs_cow`]. It borrows the coefficients for
+/// read-only uses and only copies (and pads) them on the first `to_mut`.
+pub struct EvaluationDomainCow<'a, E: Engine, G: Group<E>> {
+    state: CowState<'a, E, G>,
+}
+
+enum CowState<'a, E: Engine, G: Group<E>> {
+    /// The unpadded coefficients of a domain of size `2^exp`.
+    Borrowed {
+        coeffs: &'a [G],
+        exp: u32,
+    },
+    Owned(EvaluationDomain<E, G>),
+}
+
+impl<'a, E: Engine, G: Group<E>> AsRef<[G]> for EvaluationDomainCow<'a, E, G> {
+    /// Returns the coefficients, without the zero padding while borrowed.
+    fn as_ref(&self) -> &[G] {
+        match &self.state {
+            CowState::Borrowed { coeffs, .. } => coeffs,
+            CowState::Owned(domain) => &domain.coeffs,
+        }
+    }
+}
+
+impl<'a, E: Engine, G: Group<E>> EvaluationDomainCow<'a, E, G> {
+    /// Returns whether the coefficients were copied already.
+    pub fn is_owned(&self) -> bool {
+        matches!(self.state, CowState::Owned(_))
+    }
+
+    /// See [`EvaluationDomain::z`].
+    pub fn z(&self, tau: &E::Fr) -> E::Fr {
+        match &self.state {
+            CowState::Borrowed { exp, .. } => {
+                let mut tmp = tau.pow(&[1u64 << exp]);
+                tmp.sub_assign(&E::Fr::one());
+                tmp
+            }
+            CowState::Owned(domain) => domain.z(tau),
+        }
+    }
+
+    /// Returns the domain for mutation, copying the borrowed coefficients
+    /// into an owned, padded domain first if necessary.
+    pub fn to_mut(&mut self) -> &mut EvaluationDomain<E, G> {
+        if let CowState::Borrowed { coeffs, exp } = self.state {
+            let mut owned = Vec::with_capacity(1 << exp);
+            owned.extend_from_slice(coeffs);
+            owned.resize(1 << exp, G::group_zero());
+            self.state = CowState::Owned(EvaluationDomain::from_padded_coeffs(
+                owned,
+                exp,
+                Form::Coefficients,
+            ));
+        }
+
+        match &mut self.state {
+            CowState::Owned(domain) => domain,
+            CowState::Borrowed { .. } => unreachable!(),
+        }
+    }
+
+    /// Returns the owned domain, copying the coefficients if necessary.
+    pub fn into_owned(mut self) -> EvaluationDomain<E, G> {
+        self.to_mut();
+        match self.state {
+            CowState::Owned(domain) => domain,
+            CowState::Borrowed { .. } => unreachable!(),
+        }
+    }
+}
+
+impl<'a, E: Engine> EvaluationDomainCow<'a, E, Scalar<E>> {
+    /// See [`EvaluationDomain::evaluate`].
+    pub fn evaluate(&self, point: &E::Fr) -> E::Fr {
+        horner::<E>(scalars_as_fr(self.as_ref()), point)
+    }
+}
+
+/// A [`Group`] whose elements can be kept in the device memory of a GPU FFT
+/// kernel. Once uploaded, the kernel's on-device operations (e.g.
+/// `FFTKernel::radix_fft_on_device` and `FFTKernel::mul_by_field_on_device`)
+/// can be chained without copying the data back to the host in between.
+pub trait GpuGroup<E: Engine>: Group<E> {
+    /// Copies `values` into the kernel's device buffer.
+    fn upload(kern: &mut gpu::FFTKernel<E>, values: &[Self]) -> gpu::GPUResult<()>;
+
+    /// Copies the kernel's device buffer back into `values`.
+    fn download(kern: &mut gpu::FFTKernel<E>, values: &mut [Self]) -> gpu::GPUResult<()>;
+
+    /// Adds `values` to the kernel's device buffer.
+    fn add_assign_on_device(kern: &mut gpu::FFTKernel<E>, values: &[Self]) -> gpu::GPUResult<()>;
+
+    /// Subtracts `values` from the kernel's device buffer.
+    fn sub_assign_on_device(kern: &mut gpu::FFTKernel<E>, values: &[Self]) -> gpu::GPUResult<()>;
+}
+
+impl<E: Engine> GpuGroup<E> for Scalar<E> {
+    fn upload(kern: &mut gpu::FFTKernel<E>, values: &[Self]) -> gpu::GPUResult<()> {
+        kern.upload(scalars_as_fr(values))
+    }
+
+    fn download(kern: &mut gpu::FFTKernel<E>, values: &mut [Self]) -> gpu::GPUResult<()> {
+        kern.download(scalars_as_fr_mut(values))
+    }
+
+    fn add_assign_on_device(kern: &mut gpu::FFTKernel<E>, values: &[Self]) -> gpu::GPUResult<()> {
+        kern.add_assign_on_device(scalars_as_fr(values))
+    }
+
+    fn sub_assign_on_device(kern: &mut gpu::FFTKernel<E>, values: &[Self]) -> gpu::GPUResult<()> {
+        kern.sub_assign_on_device(scalars_as_fr(values))
+    }
+}
+
+/// An [`EvaluationDomain`] whose coefficients live in the device buffer of a
+/// GPU FFT kernel, created by [`EvaluationDomain::upload`]. Transforms run on
+/// the device until the coefficients are copied back with `download`.
+pub struct DeviceDomain<'a, E: Engine, G: GpuGroup<E>> {
+    kern: &'a mut gpu::FFTKernel<E>,
+    exp: u32,
+    omega: E::Fr,
+    omegainv: E::Fr,
+    geninv: E::Fr,
+    minv: E::Fr,
+    form: Form,
+    _group: PhantomData<G>,
+}
+
+impl<'a, E: Engine, G: GpuGroup<E>> DeviceDomain<'a, E, G> {
+    #[must_use = "the device buffer is garbage if the FFT kernel failed"]
+    pub fn fft(&mut self) -> gpu::GPUResult<()> {
+        self.kern.radix_fft_on_device(&self.omega, self.exp)?;
+        self.form = Form::Evaluations;
+        Ok(())
+    }
+
+    #[must_use = "the device buffer is garbage if the inverse FFT kernel failed"]
+    pub fn ifft(&mut self) -> gpu::GPUResult<()> {
+        self.kern.radix_fft_on_device(&self.omegainv, self.exp)?;
+        self.kern
+            .mul_by_field_on_device(&self.minv, 1 << self.exp)?;
+        self.form = Form::Coefficients;
+        Ok(())
+    }
+
+    /// Like [`EvaluationDomain::add_assign`], on the device. Only `other` is
+    /// copied to the device, `self` stays there.
+    pub fn add_assign(&mut self, other: &EvaluationDomain<E, G>) -> gpu::GPUResult<()> {
+        if let Some(other) = other.padded_copy() {
+            return self.add_assign(&other);
+        }
+        assert_eq!(1 << self.exp, other.coeffs.len());
+
+        G::add_assign_on_device(self.kern, &other.coeffs)
+    }
+
+    /// Like [`EvaluationDomain::sub_assign`], on the device. Only `other` is
+    /// copied to the device, `self` stays there.
+    pub fn sub_assign(&mut self, other: &EvaluationDomain<E, G>) -> gpu::GPUResult<()> {
+        if let Some(other) = other.padded_copy() {
+            return self.sub_assign(&other);
+        }
+        assert_eq!(1 << self.exp, other.coeffs.len());
+
+        G::sub_assign_on_device(self.kern, &other.coeffs)
+    }
+
+    /// Copies the coefficients back to the host.
+    pub fn download(self) -> gpu::GPUResult<EvaluationDomain<E, G>> {
+        let mut coeffs = vec![G::group_zero(); 1 << self.exp];
+        G::download(self.kern, &mut coeffs)?;
+
+        Ok(EvaluationDomain {
+            unpadded_len: coeffs.len(),
+            coeffs,
+            exp: self.exp,
+            omega: self.omega,
+            omegainv: self.omegainv,
+            geninv: self.geninv,
+            minv: self.minv,
+            form: self.form,
+            twiddles: None,
+            max_log_tasks: None,
+        })
+    }
+}
+
+impl<E: Engine, G: GpuGroup<E>> EvaluationDomain<E, G> {
+    /// Copies the coefficients into the device buffer of `kern`, e.g. one
+    /// obtained through `LockedFFTKernel::get_kernel`, so that several
+    /// transforms can run without copying the data back in between.
+    pub fn upload<'a>(
+        &self,
+        kern: &'a mut gpu::FFTKernel<E>,
+    ) -> gpu::GPUResult<DeviceDomain<'a, E, G>> {
+        if let Some(padded) = self.padded_copy() {
+            return padded.upload(kern);
+        }
+        if self.coeffs.len() > kern.max_len() {
+            return Err(gpu::GPUError::Simple(
+                "Domain does not fit into the FFT kernel!",
+            ));
+        }
+        G::upload(kern, &self.coeffs)?;
+
+        Ok(DeviceDomain {
+            kern,
+            exp: self.exp,
+            omega: self.omega,
+            omegainv: self.omegainv,
+            geninv: self.geninv,
+            minv: self.minv,
+            form: self.form,
+            _group: PhantomData,
+        })
+    }
+
+    /// Runs an `fft`, hands the evaluations to `f` while they stay on the
+    /// device (e.g. to `add_assign` other evaluations) and runs the `ifft`,
+    /// copying the coefficients to the device and back only once. `kern` keeps
+    /// its device buffers between calls, so round trips of the same size
+    /// don't allocate. Unlike `fft`, this doesn't fall back to the CPU.
+    pub fn fft_then_ifft<F>(
+        &mut self,
+        kern: &mut gpu::LockedFFTKernel<E>,
+        mut f: F,
+    ) -> gpu::GPUResult<()>
+    where
+        F: FnMut(&mut DeviceDomain<'_, E, G>) -> gpu::GPUResult<()>,
+    {
+        self.pad();
+        let coeffs = kern.with(|k: &mut gpu::FFTKernel<E>| {
+            let mut device = self.upload(k)?;
+            device.fft()?;
+            f(&mut device)?;
+            device.ifft()?;
+            Ok(device.download()?.coeffs)
+        })?;
+        self.coeffs = coeffs;
+
+        Ok(())
+    }
+}
+
+/// Computes cyclic convolutions of two coefficient vectors on the GPU. The
+/// forward transforms, the pointwise product and the inverse transform all run
+/// on the device, only the inputs and the result are copied.
+pub struct GpuConvolution<'a, E: Engine> {
+    kern: &'a mut gpu::LockedFFTKernel<E>,
+}
+
+impl<'a, E: Engine> GpuConvolution<'a, E> {
+    pub fn new(kern: &'a mut gpu::LockedFFTKernel<E>) -> Self {
+        GpuConvolution { kern }
+    }
+
+    /// Returns the coefficients of `a * b mod (x^n - 1)` for `n = 2^log_n`,
+    /// i.e. what `mul_assign` computes between the transforms. Both inputs are
+    /// zero-padded to `n`. Unlike `best_fft`, this doesn't fall back to the CPU.
+    pub fn convolve(&mut self, a: &[E::Fr], b: &[E::Fr], log_n: u32) -> gpu::GPUResult<Vec<E::Fr>> {
+        let n = 1 << log_n;
+        assert!(a.len() <= n && b.len() <= n);
+
+        let omega = RootsOfUnity::<E>::get().omega_for_exp(log_n);
+        let omegainv = omega.inverse().unwrap();
+        let minv = domain_size::<E::Fr>(log_n).inverse().unwrap();
+
+        let mut a = a.to_vec();
+        a.resize(n, E::Fr::zero());
+        let mut b = b.to_vec();
+        b.resize(n, E::Fr::zero());
+
+        let mut result = vec![E::Fr::zero(); n];
+        self.kern.with(|k: &mut gpu::FFTKernel<E>| {
+            if n > k.max_len() {
+                return Err(gpu::GPUError::Simple(
+                    "Convolution does not fit into the FFT kernel!",
+                ));
+            }
+            k.upload(&b)?;
+            k.radix_fft_on_device(&omega, log_n)?;
+            k.stash_on_device()?;
+            k.upload(&a)?;
+            k.radix_fft_on_device(&omega, log_n)?;
+            k.mul_stashed_on_device(n as u32)?;
+            k.radix_fft_on_device(&omegainv, log_n)?;
+            k.mul_by_field_on_device(&minv, n as u32)?;
+            k.download(&mut result)
+        })?;
+
+        Ok(result)
+    }
+}
+
+/// Computes `omega^k` for `k < 2^log_n / 2`, the twiddle factors of an FFT of
+/// size `2^log_n` over `omega`.
+fn twiddle_table<F: Field>(omega: &F, log_n: u32, worker: &Worker) -> Vec<F> {
+    powers_of(omega, (1 << log_n) >> 1, worker)
+}
+
+/// Computes the running products of `v` with a two-pass parallel scan: the
+/// products of the chunks are computed in parallel and scanned serially, then
+/// each chunk scans its elements, starting from the product of all chunks
+/// before it.
+fn prefix_product<F: Field>(v: &[F], worker: &Worker) -> Vec<F> {
+    let chunk = worker.get_chunk_size(v.len());
+    let mut offsets = vec![F::one(); v.chunks(chunk).len()];
+    worker.scope(0, |scope, _| {
+        for (product, v) in offsets.iter_mut().zip(v.chunks(chunk)) {
+            scope.spawn(move |_| {
+                for v in v {
+                    product.mul_assign(v);
+                }
+            });
+        }
+    });
+
+    // Turn the chunk products into the products of all preceding chunks
+    let mut acc = F::one();
+    for offset in offsets.iter_mut() {
+        let product = *offset;
+        *offset = acc;
+        acc.mul_assign(&product);
+    }
+
+    let mut products = v.to_vec();
+    worker.in_place_scope(&mut products, |i, v| {
+        let mut acc = offsets[i];
+        for v in v {
+            acc.mul_assign(v);
+            *v = acc;
+        }
+    });
+    products
+}
+
+/// Computes `g^k` for `k < len`.
+fn powers_of<F: Field>(g: &F, len: usize, worker: &Worker) -> Vec<F> {
+    let mut powers = vec![F::zero(); len];
+    let chunk = worker.get_chunk_size(len);
+    worker.in_place_scope(&mut powers, |i, v| {
+        let mut u = g.pow(&[(i * chunk) as u64]);
+        for v in v.iter_mut() {
+            *v = u;
+            u.mul_assign(g);
+        }
+    });
+    powers
+}
+
+/// The twiddle factors of a forward or inverse transform, taken from a table
+/// built by `twiddle_table` for the forward transform.
+#[derive(Clone, Copy)]
+struct Twiddles<'a, F: Field> {
+    powers: &'a [F],
+    inverse: bool,
+}
+
+impl<'a, F: Field> Twiddles<'a, F> {
+    /// Returns `omega^k`, or `omega^-k` for the inverse transform.
+    fn get(&self, k: usize) -> F {
+        if self.inverse && k != 0 {
+            // omega^-k = omega^(n - k) = -omega^(n/2 - k)
+            let mut w = self.powers[self.powers.len() - k];
+            w.negate();
+            w
+        } else {
+            self.powers[k]
+        }
+    }
+}
+
+/// Everything a CPU FFT of size `2^exp` needs besides its input, i.e. the
+/// roots, `1/m` and the twiddle factors, built once and reused by every
+/// `forward` and `inverse` transform of that size.
+pub struct FftPlan<E: ScalarEngine> {
+    exp: u32,
+    omega: E::Fr,
+    omegainv: E::Fr,
+    minv: E::Fr,
+    twiddles: Vec<E::Fr>,
+}
+
+impl<E: ScalarEngine> FftPlan<E> {
+    pub fn new(exp: u32, worker: &Worker) -> Result<FftPlan<E>, SynthesisError> {
+        if exp >= E::Fr::S {
+            return Err(SynthesisError::PolynomialDegreeTooLarge);
+        }
+
+        let omega = RootsOfUnity::<E>::get().omega_for_exp(exp);
+        Ok(FftPlan {
+            exp,
+            omega,
+            omegainv: omega.inverse().unwrap(),
+            minv: domain_size::<E::Fr>(exp).inverse().unwrap(),
+            twiddles: twiddle_table(&omega, exp, worker),
+        })
+    }
+
+    /// Returns log2 of the size of the transforms.
+    pub fn exp(&self) -> u32 {
+        self.exp
+    }
+
+    /// Replaces the coefficients `a` by the evaluations at the roots of unity.
+    pub fn forward(&self, a: &mut [E::Fr], worker: &Worker) {
+        self.transform(fr_as_scalars_mut::<E>(a), worker, false);
+    }
+
+    /// Replaces the evaluations `a` by the coefficients, undoing `forward`.
+    pub fn inverse(&self, a: &mut [E::Fr], worker: &Worker) {
+        self.transform(fr_as_scalars_mut::<E>(a), worker, true);
+    }
+
+    fn transform<T: Group<E>>(&self, a: &mut [T], worker: &Worker, inverse: bool) {
+        assert_eq!(a.len(), 1 << self.exp);
+
+        let options = CpuFftOptions {
+            twiddles: Some(Twiddles {
+                powers: &self.twiddles,
+                inverse,
+            }),
+            max_log_tasks: None,
+        };
+        let omega = if inverse { &self.omegainv } else { &self.omega };
+        cpu_fft(a, worker, omega, self.exp, None, options).expect("FFT is not cancellable");
+
+        if inverse {
+            let minv = self.minv;
+            worker.scope(a.len(), |scope, chunk| {
+                for v in a.chunks_mut(chunk) {
+                    scope.spawn(move |_| {
+                        for v in v {
+                            v.group_mul_assign(&minv);
+                        }
+                    });
+                }
+            });
+        }
+    }
+}
+
+fn best_fft<E: Engine, T: Group<E>>(
+    kern: &mut Option<gpu::LockedFFTKernel<E>>,
+    a: &mut [T],
+    worker: &Worker,
+    omega: &E::Fr,
+    log_n: u32,
+    cancel: Option<&AtomicBool>,
+    options: CpuFftOptions<E::Fr>,
+) -> gpu::GPUResult<FftBackend> {
+    if is_cancelled(cancel) {
+        return Err(gpu::GPUError::Cancelled);
+    }
+
+    if log_n >= gpu_fft_threshold() {
+        if let Some(ref mut kern) = kern {
+            #[cfg(all(feature = "gpu-verify", debug_assertions))]
+            let input = a.to_vec();
+
+            if kern
+                .with(|k: &mut gpu::FFTKernel<E>| gpu_fft(k, a, omega, log_n))
+                .is_ok()
+            {
+                #[cfg(all(feature = "gpu-verify", debug_assertions))]
+                verify_gpu_fft(input, a, worker, omega, log_n);
+
+                return Ok(FftBackend::Gpu);
+            }
+        }
+    }
+
+    cpu_fft(a, worker, omega, log_n, cancel, options)?;
+    Ok(FftBackend::Cpu)
+}
+
+/// Recomputes the FFT of `input` on the CPU and asserts that it matches the
+/// GPU result `a`.
+#[cfg(all(feature = "gpu-verify", debug_assertions))]
+fn verify_gpu_fft<E: ScalarEngine, T: Group<E>>(
+    mut input: Vec<T>,
+    a: &mut [T],
+    worker: &Worker,
+    omega: &E::Fr,
+    log_n: u32,
+) {
+    cpu_fft(
+        &mut input,
+        worker,
+        omega,
+        log_n,
+        None,
+        CpuFftOptions::default(),
+    )
+    .expect("CPU FFT failed");
+    debug_assert!(
+        group_as_fr_mut::<E, T>(&mut input) == group_as_fr_mut::<E, T>(a),
+        "GPU FFT result does not match the CPU result (log_n = {})",
+        log_n
+    );
+}
+
+fn cpu_fft<E: ScalarEngine, T: Group<E>>(
+    a: &mut [T],
+    worker: &Worker,
+    omega: &E::Fr,
+    log_n: u32,
+    cancel: Option<&AtomicBool>,
+    options: CpuFftOptions<E::Fr>,
+) -> gpu::GPUResult<()> {
+    let twiddles = options.twiddles;
+    match planned_log_cpus(worker, log_n, options.max_log_tasks) {
+        0 => serial_fft_cancellable(a, omega, log_n, cancel, twiddles),
+        log_cpus => parallel_fft(a, worker, omega, log_n, log_cpus, cancel, twiddles),
+    }
+}
+
+/// Per-domain settings of the CPU FFT, beyond its input.
+#[derive(Clone, Copy)]
+struct CpuFftOptions<'a, F: Field> {
+    /// See `EvaluationDomain::precompute_twiddles`.
+    twiddles: Option<Twiddles<'a, F>>,
+    /// See `EvaluationDomain::set_max_log_tasks`.
+    max_log_tasks: Option<u32>,
+}
+
+impl<'a, F: Field> Default for CpuFftOptions<'a, F> {
+    fn default() -> Self {
+        CpuFftOptions {
+            twiddles: None,
+            max_log_tasks: None,
+        }
+    }
+}
+
+/// Returns the `log_cpus` a CPU FFT of size `2^log_n` is split into, `0` if it
+/// runs serially. `max_log_tasks` caps it below `worker.log_num_cpus()`.
+fn planned_log_cpus(worker: &Worker, log_n: u32, max_log_tasks: Option<u32>) -> u32 {
+    let log_cpus = worker
+        .log_num_cpus()
+        .min(max_log_tasks.unwrap_or(std::u32::MAX));
+    if log_n <= log_cpus {
+        0
+    } else {
+        log_cpus
+    }
+}
+
+fn is_cancelled(cancel: Option<&AtomicBool>) -> bool {
+    cancel.map_or(false, |cancel| cancel.load(Ordering::Relaxed))
+}
+
+/// Returns the smallest `log_n` for which FFTs are offloaded to the GPU.
+pub fn gpu_fft_threshold() -> u32 {
+    GPU_FFT_MIN_LOG_N.load(Ordering::Relaxed)
+}
+
+/// Sets the smallest `log_n` for which FFTs are offloaded to the GPU. Smaller
+/// FFTs run on the CPU even if a kernel is available.
+pub fn set_gpu_fft_threshold(log_n: u32) {
+    GPU_FFT_MIN_LOG_N.store(log_n, Ordering::Relaxed);
+}
+
+/// Times the GPU and CPU FFT paths for increasing domain sizes and returns the
+/// smallest `log_n` at which the GPU wins, suitable for [`set_gpu_fft_threshold`].
+/// Returns `E::Fr::S` (i.e. never use the GPU) if the GPU is unavailable or
+/// never faster up to `2^CALIBRATION_MAX_LOG_N` elements.
+pub fn calibrate_fft_threshold<E: Engine>(
+    kern: &mut gpu::LockedFFTKernel<E>,
+    worker: &Worker,
+) -> u32 {
+    // The first GPU call pays for kernel initialization, keep it out of the timings.
+    let mut warmup = vec![Scalar::<E>(E::Fr::one()); 2];
+    let omega = E::Fr::one();
+    if kern
+        .with(|k: &mut gpu::FFTKernel<E>| gpu_fft(k, &mut warmup, &omega, 1))
+        .is_err()
+    {
+        return E::Fr::S;
+    }
+
+    for log_n in 1..=CALIBRATION_MAX_LOG_N {
+        let domain = match EvaluationDomain::<E, Scalar<E>>::from_coeffs(vec![
+            Scalar(E::Fr::one());
+            1 << log_n
+        ]) {
+            Ok(domain) => domain,
+            Err(_) => break,
+        };
+
+        let mut a = domain.coeffs.clone();
+        let now = Instant::now();
+        if kern
+            .with(|k: &mut gpu::FFTKernel<E>| gpu_fft(k, &mut a, &domain.omega, log_n))
+            .is_err()
+        {
+            break;
+        }
+        let gpu_dur = now.elapsed();
+
+        let mut a = domain.coeffs;
+        let now = Instant::now();
+        if cpu_fft(
+            &mut a,
+            worker,
+            &domain.omega,
+            log_n,
+            None,
+            CpuFftOptions::default(),
+        )
+        .is_err()
+        {
+            break;
+        }
+        let cpu_dur = now.elapsed();
+
+        if gpu_dur < cpu_dur {
+            info!("GPU FFT is faster than CPU from 2^{} elements.", log_n);
+            return log_n;
+        }
+    }
+
+    E::Fr::S
+}
+
+/// The `2^exp`-th primitive roots of unity of `E::Fr`, for every `exp` up to
+/// `E::Fr::S`, so that constructing a domain doesn't need to square
+/// `E::Fr::root_of_unity()` down to the right order every time.
+pub struct RootsOfUnity<E: ScalarEngine> {
+    /// `roots[exp]` has order `2^exp`.
+    roots: Vec<E::Fr>,
+}
+
+impl<E: ScalarEngine> RootsOfUnity<E> {
+    /// Returns the table for `E`, which is computed on first use and then
+    /// shared by all threads.
+    pub fn get() -> Arc<Self> {
+        let key = TypeId::of::<E>();
+        let cached = ROOTS_OF_UNITY.read().unwrap().get(&key).cloned();
+        let table = match cached {
+            Some(table) => table,
+            None => {
+                // Computed without holding the lock. If another thread got
+                // there first, its table is kept.
+                let table: Arc<dyn Any + Send + Sync> = Arc::new(Self::compute());
+                let mut tables = ROOTS_OF_UNITY.write().unwrap();
+                tables.entry(key).or_insert(table).clone()
+            }
+        };
+        table
+            .downcast::<Self>()
+            .expect("tables are keyed by their engine")
+    }
+
+    fn compute() -> Self {
+        let mut roots = vec![E::Fr::root_of_unity(); E::Fr::S as usize + 1];
+        for exp in (0..E::Fr::S as usize).rev() {
+            roots[exp] = roots[exp + 1];
+            roots[exp].square();
+        }
+        RootsOfUnity { roots }
+    }
+
+    /// Returns the primitive `2^exp`-th root of unity. Panics if
+    /// `exp > E::Fr::S`.
+    pub fn omega_for_exp(&self, exp: u32) -> E::Fr {
+        self.roots[exp as usize]
+    }
+}
+
+/// The methods whose running time the `self-profile` feature accumulates,
+/// see [`domain_timing_report`].
+#[derive(Clone, Copy)]
+enum ProfiledMethod {
+    Fft,
+    Ifft,
+    CosetFft,
+    IcosetFft,
+    MulAssign,
+    AddAssign,
+    SubAssign,
+    DivideByZOnCoset,
+    DistributePowers,
+}
+
+#[cfg(feature = "self-profile")]
+const PROFILED_METHODS: [&str; 9] = [
+    "fft",
+    "ifft",
+    "coset_fft",
+    "icoset_fft",
+    "mul_assign",
+    "add_assign",
+    "sub_assign",
+    "divide_by_z_on_coset",
+    "distribute_powers",
+];
+
+/// Total nanoseconds spent in each `ProfiledMethod`, and the number of calls.
+#[cfg(feature = "self-profile")]
+static PROFILE_NANOS: [AtomicU64; 9] = [
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+];
+#[cfg(feature = "self-profile")]
+static PROFILE_CALLS: [AtomicU64; 9] = [
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+];
+
+/// Adds the time until it is dropped to the total of its method. Without the
+/// `self-profile` feature it does nothing.
+struct ProfileTimer {
+    #[cfg(feature = "self-profile")]
+    method: ProfiledMethod,
+    #[cfg(feature = "self-profile")]
+    start: Instant,
+}
+
+impl ProfileTimer {
+    #[cfg(feature = "self-profile")]
+    fn start(method: ProfiledMethod) -> Self {
+        ProfileTimer {
+            method,
+            start: Instant::now(),
+        }
+    }
+
+    #[cfg(not(feature = "self-profile"))]
+    fn start(_: ProfiledMethod) -> Self {
+        ProfileTimer {}
+    }
+}
+
+#[cfg(feature = "self-profile")]
+impl Drop for ProfileTimer {
+    fn drop(&mut self) {
+        let nanos = self.start.elapsed().as_nanos() as u64;
+        PROFILE_NANOS[self.method as usize].fetch_add(nanos, Ordering::Relaxed);
+        PROFILE_CALLS[self.method as usize].fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Returns the number of calls and the total time spent in each of the
+/// profiled domain methods (`fft`, `ifft`, `mul_assign`, ...) so far, across
+/// all threads. The times are inclusive, e.g. `coset_fft` includes its `fft`.
+#[cfg(feature = "self-profile")]
+pub fn domain_timing_report() -> Vec<(&'static str, u64, Duration)> {
+    PROFILED_METHODS
+        .iter()
+        .zip(PROFILE_CALLS.iter().zip(PROFILE_NANOS.iter()))
+        .map(|(method, (calls, nanos))| {
+            (
+                *method,
+                calls.load(Ordering::Relaxed),
+                Duration::from_nanos(nanos.load(Ordering::Relaxed)),
+            )
+        })
+        .collect()
+}
+
+/// Resets the totals of [`domain_timing_report`].
+#[cfg(feature = "self-profile")]
+pub fn reset_domain_timing() {
+    for counter in PROFILE_CALLS.iter().chain(PROFILE_NANOS.iter()) {
+        counter.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Element-wise operations whose chunk size is tuned separately, as their
+/// per-element costs differ widely.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum DomainOp {
+    MulAssign,
+    AddAssign,
+    SubAssign,
+    DivideByZ,
+    DistributePowers,
+}
+
+impl DomainOp {
+    const COUNT: usize = DomainOp::DistributePowers as usize + 1;
+}
+
+/// Returns the entry of `CHUNK_TUNING_BEST` for a `CHUNK_TUNING` key.
+fn chunk_tuning_slot((op, log_n): (DomainOp, u32)) -> &'static AtomicUsize {
+    &CHUNK_TUNING_BEST[op as usize * 64 + log_n as usize]
+}
+
+#[derive(Default)]
+struct ChunkTuning {
+    /// Seconds per element, for the candidates tried so far.
+    timings: Vec<f64>,
+    /// Tasks per thread of the fastest candidate, once all were tried.
+    best: Option<usize>,
+}
+
+/// Enables or disables the chunk size autotuner. While enabled, the first
+/// calls of `mul_assign`, `add_assign`, `sub_assign`, `divide_by_z_on_coset`
+/// and `distribute_powers` on domains of each size each run with one of
+/// `CHUNK_TUNING_CANDIDATES` tasks per thread, i.e. more and smaller tasks than
+/// [`Worker::scope`] uses; later calls use the fastest. While disabled (the
+/// default), the chunk size of [`Worker::scope`] is used.
+pub fn set_chunk_tuning(enabled: bool) {
+    CHUNK_TUNING_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Like `Worker::scope`, with the chunk size picked by the autotuner for `op`.
+fn tuned_scope<'a, F>(worker: &Worker, op: DomainOp, elements: usize, f: F)
+where
+    F: FnOnce(&rayon::Scope<'a>, usize) + Send,
+{
+    if !CHUNK_TUNING_ENABLED.load(Ordering::Relaxed) {
+        return worker.scope(elements, f);
+    }
+
+    // Timings of small inputs don't carry over to large ones, and vice versa
+    let key = (op, elements.next_power_of_two().trailing_zeros());
+    let (tasks_per_thread, timed) = match chunk_tuning_slot(key).load(Ordering::Relaxed) {
+        0 => {
+            let mut tunings = CHUNK_TUNING.lock().unwrap();
+            let tuning = tunings.entry(key).or_default();
+            match tuning.best {
+                Some(best) => (best, None),
+                None => {
+                    let candidate = tuning.timings.len();
+                    (CHUNK_TUNING_CANDIDATES[candidate], Some(candidate))
+                }
+            }
+        }
+        best => (best, None),
+    };
+
+    let chunk_size = (worker.get_chunk_size(elements) / tasks_per_thread).max(1);
+    let now = Instant::now();
+    worker.scope_with_chunk_size(chunk_size, f);
+    let per_element = now.elapsed().as_secs_f64() / elements.max(1) as f64;
+
+    if let Some(candidate) = timed {
+        let mut tunings = CHUNK_TUNING.lock().unwrap();
+        let tuning = tunings.entry(key).or_default();
+        // Concurrent calls may have timed the same candidate already
+        if tuning.best.is_none() && tuning.timings.len() == candidate {
+            tuning.timings.push(per_element);
+            if tuning.timings.len() == CHUNK_TUNING_CANDIDATES.len() {
+                let (fastest, _) =
+                    tuning
+                        .timings
+                        .iter()
+                        .enumerate()
+                        .fold(
+                            (0, f64::INFINITY),
+                            |min, (i, &t)| if t < min.1 { (i, t) } else { min },
+                        );
+                tuning.best = Some(CHUNK_TUNING_CANDIDATES[fastest]);
+                chunk_tuning_slot(key).store(CHUNK_TUNING_CANDIDATES[fastest], Ordering::Relaxed);
+                info!(
+                    "{:?} of 2^{} elements runs fastest with {} tasks per thread.",
+                    op, key.1, CHUNK_TUNING_CANDIDATES[fastest]
+                );
+            }
+        }
+    }
+}
+
+/// Views the elements of a domain as field elements, without copying.
+fn group_as_fr_mut<E: ScalarEngine, T: Group<E>>(a: &mut [T]) -> &mut [E::Fr] {
+    // EvaluationDomain module is supposed to work only with E::Fr elements, and not CurveProjective
+    // points. The Bellman authors have implemented an unnecessarry abstraction called Group<E>
+    // which is implemented for both PrimeField and CurveProjective elements. As nowhere in the code
+    // is the CurveProjective version used, T and E::Fr are guaranteed to be equal and thus have same
+    // size.
+    // For compatibility/performance reasons we decided to transmute the array to the desired type
+    // as it seems safe and needs less modifications in the current structure of Bellman library.
+    unsafe { std::mem::transmute::<&mut [T], &mut [E::Fr]>(a) }
+}
+
+#[must_use = "`a` is garbage if the GPU FFT failed"]
+pub fn gpu_fft<E: Engine, T: Group<E>>(
+    kern: &mut gpu::FFTKernel<E>,
+    a: &mut [T],
+    omega: &E::Fr,
+    log_n: u32,
+) -> gpu::GPUResult<()> {
+    let a = group_as_fr_mut::<E, T>(a);
+    kern.radix_fft(a, omega, log_n)?;
+    Ok(())
+}
+
+pub fn serial_fft<E: ScalarEngine, T: Group<E>>(a: &mut [T], omega: &E::Fr, log_n: u32) {
+    // Without a cancellation flag the FFT always runs to completion.
+    let _ = serial_fft_cancellable(a, omega, log_n, None, None);
+}
+
+/// Runs `serial_fft` on each of `polys`, which must all have `2^log_n`
+/// elements, in parallel across the batch. The twiddle factors are computed
+/// once and shared by all of them, rather than regenerated in every stage of
+/// every transform.
+pub fn batch_fft_shared_twiddles<E: ScalarEngine>(
+    polys: &mut [Vec<E::Fr>],
+    omega: &E::Fr,
+    log_n: u32,
+    worker: &Worker,
+) {
+    assert!(polys.iter().all(|poly| poly.len() == 1 << log_n));
+
+    let powers = twiddle_table(omega, log_n, worker);
+    let twiddles = Twiddles {
+        powers: &powers,
+        inverse: false,
+    };
+    worker.scope(polys.len(), |scope, chunk| {
+        for polys in polys.chunks_mut(chunk) {
+            scope.spawn(move |_| {
+                for poly in polys {
+                    let _ = serial_fft_cancellable(
+                        fr_as_scalars_mut::<E>(poly),
+                        omega,
+                        log_n,
+                        None,
+                        Some(twiddles),
+                    );
+                }
+            });
+        }
+    });
+}
+
+/// Like `serial_fft`, but calls `on_stage` with the index of each of the
+/// `log_n` butterfly stages and the elements after it, e.g. to find the first
+/// stage at which a new FFT implementation diverges from this one.
+#[cfg(feature = "fft-trace")]
+pub fn serial_fft_traced<E: ScalarEngine, T: Group<E>>(
+    a: &mut [T],
+    omega: &E::Fr,
+    log_n: u32,
+    on_stage: impl FnMut(usize, &[T]),
+) {
+    // Without a cancellation flag the FFT always runs to completion.
+    let _ = serial_fft_staged(a, omega, log_n, None, None, on_stage);
+}
+
+/// Like `serial_fft`, but checks `cancel` between stages and takes the twiddle
+/// factors from `twiddles` if given. `twiddles` may belong to a larger domain
+/// whose root of unity is a power of `omega`, see `parallel_fft`.
+fn serial_fft_cancellable<E: ScalarEngine, T: Group<E>>(
+    a: &mut [T],
+    omega: &E::Fr,
+    log_n: u32,
+    cancel: Option<&AtomicBool>,
+    twiddles: Option<Twiddles<E::Fr>>,
+) -> gpu::GPUResult<()> {
+    serial_fft_staged(a, omega, log_n, cancel, twiddles, |_, _| {})
+}
+
+/// Like `serial_fft_cancellable`, but calls `on_stage` after every stage, see
+/// `serial_fft_traced`.
+fn serial_fft_staged<E: ScalarEngine, T: Group<E>, F: FnMut(usize, &[T])>(
+    a: &mut [T],
+    omega: &E::Fr,
+    log_n: u32,
+    cancel: Option<&AtomicBool>,
+    twiddles: Option<Twiddles<E::Fr>>,
+    mut on_stage: F,
+) -> gpu::GPUResult<()> {
+    fn bitreverse(mut n: u32, l: u32) -> u32 {
+        let mut r = 0;
+        for _ in 0..l {
+            r = (r << 1) | (n & 1);
+            n >>= 1;
+        }
+        r
+    }
+
+    let n = a.len() as u32;
+    assert_eq!(n, 1 << log_n);
+
+    // The transform of a single element is the element itself.
+    if n <= 1 {
+        return Ok(());
+    }
+
+    for k in 0..n {
+        let rk = bitreverse(k, log_n);
+        if k < rk {
+            a.swap(rk as usize, k as usize);
+        }
+    }
+
+    let mut m = 1;
+    for stage in 0..log_n {
+        if is_cancelled(cancel) {
+            return Err(gpu::GPUError::Cancelled);
+        }
+
+        let w_m = omega.pow(&[u64::from(n / (2 * m))]);
+        // Distance between the twiddles of this stage in the table
+        let step = twiddles.map_or(0, |twiddles| twiddles.powers.len() / m as usize);
+
+        let mut k = 0;
+        while k < n {
+            let mut w = E::Fr::one();
+            for j in 0..m {
+                let mut t = a[(k + j + m) as usize];
+                t.group_mul_assign(&twiddles.map_or(w, |twiddles| twiddles.get(j as usize * step)));
+                let mut tmp = a[(k + j) as usize];
+                tmp.group_sub_assign(&t);
+                a[(k + j + m) as usize] = tmp;
+                a[(k + j) as usize].group_add_assign(&t);
+                if twiddles.is_none() {
+                    w.mul_assign(&w_m);
+                }
+            }
+
+            k += 2 * m;
+        }
+
+        on_stage(stage as usize, a);
+        m *= 2;
+    }
+
+    Ok(())
+}
+
+/// Scratch space pooled by `return_scratch`, keyed by the size and alignment
+/// of the elements, number of sub-FFTs and their size. Keying by layout rather
+/// than `TypeId` works for elements that aren't `'static`, too.
+#[cfg(feature = "scratch-pool")]
+type ScratchPool = HashMap<(usize, usize, usize, usize), Vec<ScratchBuffer>>;
+
+/// The allocation of a pooled scratch vector, which vectors of any element type
+/// with the same size and alignment can take over.
+#[cfg(feature = "scratch-pool")]
+struct ScratchBuffer {
+    ptr: *mut u8,
+    layout: Layout,
+}
+
+#[cfg(feature = "scratch-pool")]
+impl Drop for ScratchBuffer {
+    fn drop(&mut self) {
+        unsafe { std::alloc::dealloc(self.ptr, self.layout) }
+    }
+}
+
+#[cfg(feature = "scratch-pool")]
+thread_local! {
+    static SCRATCH_POOL: RefCell<ScratchPool> = RefCell::new(HashMap::new());
+}
+
+/// Returns `count` zeroed vectors of length `len` for `parallel_fft`. With the
+/// `scratch-pool` feature, the allocations of vectors returned through
+/// `return_scratch` on this thread are reused.
+fn take_scratch<E: ScalarEngine, T: Group<E>>(count: usize, len: usize) -> Vec<Vec<T>> {
+    #[cfg(feature = "scratch-pool")]
+    {
+        let key = (size_of::<T>(), align_of::<T>(), count, len);
+        if let Some(pooled) = SCRATCH_POOL.with(|pool| pool.borrow_mut().remove(&key)) {
+            return pooled
+                .into_iter()
+                .map(|buffer| {
+                    let buffer = ManuallyDrop::new(buffer);
+                    let ptr = buffer.ptr as *mut T;
+                    let capacity = buffer.layout.size() / size_of::<T>();
+                    // The allocation has the layout of a `Vec<T>` of `capacity`
+                    // elements, but may hold elements of another type, so it is
+                    // initialized before it is handed out.
+                    unsafe {
+                        for i in 0..len {
+                            ptr.add(i).write(T::group_zero());
+                        }
+                        Vec::from_raw_parts(ptr, len, capacity)
+                    }
+                })
+                .collect();
+        }
+    }
+
+    vec![vec![T::group_zero(); len]; count]
+}
+
+/// Hands scratch space obtained from `take_scratch` back to the pool of this
+/// thread, or frees it without the `scratch-pool` feature.
+fn return_scratch<E: ScalarEngine, T: Group<E>>(tmp: Vec<Vec<T>>) {
+    #[cfg(feature = "scratch-pool")]
+    {
+        let len = tmp.first().map_or(0, Vec::len);
+        // Empty vectors don't own an allocation
+        if len * size_of::<T>() == 0 {
+            return;
+        }
+        let key = (size_of::<T>(), align_of::<T>(), tmp.len(), len);
+        let buffers = tmp
+            .into_iter()
+            .map(|v| {
+                let mut v = ManuallyDrop::new(v);
+                ScratchBuffer {
+                    ptr: v.as_mut_ptr() as *mut u8,
+                    layout: Layout::array::<T>(v.capacity()).expect("allocated before"),
+                }
+            })
+            .collect();
+        SCRATCH_POOL.with(|pool| pool.borrow_mut().insert(key, buffers));
+    }
+    #[cfg(not(feature = "scratch-pool"))]
+    drop(tmp);
+}
+
+/// Frees the scratch space `parallel_fft` pooled on the current thread.
+#[cfg(feature = "scratch-pool")]
+pub fn clear_scratch_pool() {
+    SCRATCH_POOL.with(|pool| pool.borrow_mut().clear());
+}
+
+/// Splits the transform into `2^log_cpus` sub-FFTs run in parallel. The result
+/// is bit-identical to `serial_fft` for every `log_cpus <= log_n`: each task
+/// owns its sub-FFT and its share of the output, so the thread scheduling never
+/// affects which elements are summed in which order.
+fn parallel_fft<E: ScalarEngine, T: Group<E>>(
+    a: &mut [T],
+    worker: &Worker,
+    omega: &E::Fr,
+    log_n: u32,
+    log_cpus: u32,
+    cancel: Option<&AtomicBool>,
+    twiddles: Option<Twiddles<E::Fr>>,
+) -> gpu::GPUResult<()> {
+    assert!(log_n >= log_cpus);
+
+    let num_cpus = 1 << log_cpus;
+    let log_new_n = log_n - log_cpus;
+    let mut tmp = take_scratch::<E, T>(num_cpus, 1 << log_new_n);
+    let new_omega = omega.pow(&[num_cpus as u64]);
+
+    worker.scope(0, |scope, _| {
+        let a = &*a;
+
+        for (j, tmp) in tmp.iter_mut().enumerate() {
+            scope.spawn(move |_scope| {
+                // Shuffle into a sub-FFT
+                let omega_j = omega.pow(&[j as u64]);
+                let omega_step = omega.pow(&[(j as u64) << log_new_n]);
+
+                let mut elt = E::Fr::one();
+                for (i, tmp) in tmp.iter_mut().enumerate() {
+                    if is_cancelled(cancel) {
+                        return;
+                    }
+                    for s in 0..num_cpus {
+                        let idx = (i + (s << log_new_n)) % (1 << log_n);
+                        let mut t = a[idx];
+                        t.group_mul_assign(&elt);
+                        tmp.group_add_assign(&t);
+                        elt.mul_assign(&omega_step);
+                    }
+                    elt.mul_assign(&omega_j);
+                }
+
+                // Perform sub-FFT, a cancellation is reported once the scope ends
+                let _ = serial_fft_cancellable(tmp, &new_omega, log_new_n, cancel, twiddles);
+            });
+        }
+    });
+
+    if is_cancelled(cancel) {
+        return_scratch::<E, T>(tmp);
+        return Err(gpu::GPUError::Cancelled);
+    }
+
+    // TODO: does this hurt or help?
+    worker.scope(a.len(), |scope, chunk| {
+        let tmp = &tmp;
+
+        for (idx, a) in a.chunks_mut(chunk).enumerate() {
+            scope.spawn(move |_scope| {
+                let mut idx = idx * chunk;
+                let mask = (1 << log_cpus) - 1;
+                for a in a {
+                    #[cfg(feature = "prefetch")]
+                    {
+                        let ahead = idx + PREFETCH_DISTANCE;
+                        if ahead < (1 << log_n) {
+                            prefetch(&tmp[ahead & mask][ahead >> log_cpus]);
+                        }
+                    }
+                    *a = tmp[idx & mask][idx >> log_cpus];
+                    idx += 1;
+                }
+            });
+        }
+    });
+
+    return_scratch::<E, T>(tmp);
+
+    Ok(())
+}
+
+/// How many iterations ahead the `parallel_fft` scatter prefetches.
+#[cfg(feature = "prefetch")]
+const PREFETCH_DISTANCE: usize = 8;
+
+/// Hints the CPU to pull `value` into the cache ahead of its use. This is a
+/// no-op on architectures without a prefetch intrinsic.
+#[cfg(feature = "prefetch")]
+#[inline(always)]
+fn prefetch<T>(value: &T) {
+    #[cfg(target_arch = "x86_64")]
+    unsafe {
+        use std::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+        _mm_prefetch(value as *const T as *const i8, _MM_HINT_T0);
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    let _ = value;
+}
+
+/// Decimation-in-time FFT over a domain of size `radices.iter().product()`.
+/// The sub-FFTs of the outermost split run in parallel.
+fn mixed_radix_fft<E: ScalarEngine, T: Group<E>>(
+    a: &mut [T],
+    worker: &Worker,
+    omega: &E::Fr,
+    radices: &[u32],
+) {
+    assert_eq!(
+        a.len() as u64,
+        radices.iter().map(|&r| u64::from(r)).product::<u64>()
+    );
+    if a.len() == 1 {
+        return;
+    }
+
+    let r = radices[0] as usize;
+    let m = a.len() / r;
+    let sub_omega = omega.pow(&[r as u64]);
+
+    // Split into the r interleaved subsequences and transform each of them
+    let mut subs = (0..r)
+        .map(|j| a.iter().skip(j).step_by(r).cloned().collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    worker.scope(0, |scope, _| {
+        for sub in subs.iter_mut() {
+            scope.spawn(move |_| serial_mixed_radix_fft(sub, &sub_omega, &radices[1..]));
+        }
+    });
+
+    mixed_radix_combine(a, &subs, omega, m);
+}
+
+fn serial_mixed_radix_fft<E: ScalarEngine, T: Group<E>>(
+    a: &mut [T],
+    omega: &E::Fr,
+    radices: &[u32],
+) {
+    if a.len() == 1 {
+        return;
+    }
+
+    let r = radices[0] as usize;
+    let m = a.len() / r;
+    let sub_omega = omega.pow(&[r as u64]);
+
+    let mut subs = (0..r)
+        .map(|j| a.iter().skip(j).step_by(r).cloned().collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    for sub in subs.iter_mut() {
+        serial_mixed_radix_fft(sub, &sub_omega, &radices[1..]);
+    }
+
+    mixed_radix_combine(a, &subs, omega, m);
+}
+
+/// Combines the sub-FFTs of size `m` into `a`:
+/// `a[i] = sum_j omega^(i * j) * subs[j][i % m]`.
+fn mixed_radix_combine<E: ScalarEngine, T: Group<E>>(
+    a: &mut [T],
+    subs: &[Vec<T>],
+    omega: &E::Fr,
+    m: usize,
+) {
+    // The roots of unity of order subs.len(), omega^(s * m)
+    let zeta = omega.pow(&[m as u64]);
+    let mut zetas = vec![E::Fr::one(); subs.len()];
+    for s in 1..zetas.len() {
+        zetas[s] = zetas[s - 1];
+        zetas[s].mul_assign(&zeta);
+    }
+
+    let mut omega_k = E::Fr::one();
+    for k in 0..m {
+        for (s, zeta) in zetas.iter().enumerate() {
+            // omega^(k + s * m)
+            let mut twiddle = omega_k;
+            twiddle.mul_assign(zeta);
+
+            let mut acc = T::group_zero();
+            let mut factor = E::Fr::one();
+            for sub in subs {
+                let mut term = sub[k];
+                term.group_mul_assign(&factor);
+                acc.group_add_assign(&term);
+                factor.mul_assign(&twiddle);
+            }
+            a[k + s * m] = acc;
+        }
+        omega_k.mul_assign(omega);
+    }
+}
+
+/// Schoolbook O(n^2) polynomial multiplication, parallelized over the output
+/// coefficients. Serves as a reference for FFT-based multiplication.
+#[cfg(any(test, feature = "test-utils"))]
+pub(crate) fn naive_mul<E: ScalarEngine>(a: &[E::Fr], b: &[E::Fr], worker: &Worker) -> Vec<E::Fr> {
+    if a.is_empty() || b.is_empty() {
+        return vec![];
+    }
+
+    let mut res = vec![E::Fr::zero(); a.len() + b.len() - 1];
+    worker.scope(res.len(), |scope, chunk| {
+        for (i, res) in res.chunks_mut(chunk).enumerate() {
+            scope.spawn(move |_| {
+                for (k, res) in (i * chunk..).zip(res.iter_mut()) {
+                    // Sum a[j] * b[k - j] over all valid j
+                    let start = k.saturating_sub(b.len() - 1);
+                    let end = k.min(a.len() - 1);
+                    for j in start..=end {
+                        let mut tmp = a[j];
+                        tmp.mul_assign(&b[k - j]);
+                        res.add_assign(&tmp);
+                    }
+                }
+            });
+        }
+    });
+
+    res
+}
+
+/// Below this number of points, `evaluate_multi` evaluates directly.
+const MULTIPOINT_LEAF_SIZE: usize = 32;
+
+/// Below this number of coefficients, `poly_mul` multiplies directly.
+const NAIVE_MUL_THRESHOLD: usize = 32;
+
+/// Evaluates the polynomial with the given coefficients at `point`.
+fn horner<E: ScalarEngine>(coeffs: &[E::Fr], point: &E::Fr) -> E::Fr {
+    coeffs.iter().rev().fold(E::Fr::zero(), |mut acc, c| {
+        acc.mul_assign(point);
+        acc.add_assign(c);
+        acc
+    })
+}
+
+/// Multiplies the polynomials `a` and `b` in O(n^2).
+fn naive_poly_mul<E: ScalarEngine>(a: &[E::Fr], b: &[E::Fr]) -> Vec<E::Fr> {
+    if a.is_empty() || b.is_empty() {
+        return vec![];
+    }
+
+    let mut res = vec![E::Fr::zero(); a.len() + b.len() - 1];
+    for (i, a) in a.iter().enumerate() {
+        for (j, b) in b.iter().enumerate() {
+            let mut tmp = *a;
+            tmp.mul_assign(b);
+            res[i + j].add_assign(&tmp);
+        }
+    }
+    res
+}
+
+/// Multiplies the polynomials `a` and `b`, using FFTs unless one of them is
+/// small.
+fn poly_mul<E: Engine>(
+    a: &[E::Fr],
+    b: &[E::Fr],
+    worker: &Worker,
+) -> Result<Vec<E::Fr>, SynthesisError> {
+    if a.len().min(b.len()) <= NAIVE_MUL_THRESHOLD {
+        return Ok(naive_poly_mul::<E>(a, b));
+    }
+
+    let len = a.len() + b.len() - 1;
+    let domain = |coeffs: &[E::Fr]| {
+        let mut coeffs = coeffs.iter().map(|c| Scalar::<E>(*c)).collect::<Vec<_>>();
+        coeffs.resize(len, Scalar(E::Fr::zero()));
+        EvaluationDomain::from_coeffs(coeffs)
+    };
+    let mut a = domain(a)?;
+    let mut b = domain(b)?;
+
+    a.fft(worker, &mut None)?;
+    b.fft(worker, &mut None)?;
+    a.mul_assign(worker, &b);
+    a.ifft(worker, &mut None)?;
+
+    let mut res = a.as_fr().to_vec();
+    res.truncate(len);
+    Ok(res)
+}
+
+/// Returns `g` such that `f·g = 1 mod x^n`, using Newton iteration.
+fn poly_inverse_mod_xn<E: Engine>(
+    f: &[E::Fr],
+    n: usize,
+    worker: &Worker,
+) -> Result<Vec<E::Fr>, SynthesisError> {
+    let mut g = vec![f
+        .first()
+        .and_then(|f| f.inverse())
+        .ok_or(SynthesisError::DivisionByZero)?];
+
+    let mut len = 1;
+    while len < n {
+        len = (2 * len).min(n);
+
+        // g = g·(2 - f·g) mod x^len
+        let mut tmp = poly_mul::<E>(&f[..f.len().min(len)], &g, worker)?;
+        tmp.truncate(len);
+        for c in tmp.iter_mut() {
+            c.negate();
+        }
+        let mut two = E::Fr::one();
+        two.double();
+        tmp[0].add_assign(&two);
+
+        g = poly_mul::<E>(&g, &tmp, worker)?;
+        g.truncate(len);
+    }
+
+    Ok(g)
+}
+
+/// Returns the remainder of dividing `a` by `b`. The last coefficient of `b`
+/// must be nonzero.
+fn poly_rem<E: Engine>(
+    a: &[E::Fr],
+    b: &[E::Fr],
+    worker: &Worker,
+) -> Result<Vec<E::Fr>, SynthesisError> {
+    div_rem::<E>(a, b, worker).map(|(_, rem)| rem)
+}
+
+/// Computes the evaluations of the grand product polynomial `Z` of a
+/// permutation argument, `Z_i = ∏_{j ≤ i} numerators_j / denominators_j`.
+/// Errors with `SynthesisError::InvalidDomainSize` if the lengths differ and
+/// with `SynthesisError::DivisionByZero` if a denominator is zero.
+pub fn grand_product<E: ScalarEngine>(
+    numerators: &[E::Fr],
+    denominators: &[E::Fr],
+    worker: &Worker,
+) -> Result<Vec<E::Fr>, SynthesisError> {
+    if numerators.len() != denominators.len() {
+        return Err(SynthesisError::InvalidDomainSize);
+    }
+    if denominators.iter().any(|d| d.is_zero()) {
+        return Err(SynthesisError::DivisionByZero);
+    }
+
+    let mut ratios = denominators.to_vec();
+    batch_inversion(&mut ratios, worker);
+    let chunk = worker.get_chunk_size(ratios.len());
+    worker.in_place_scope(&mut ratios, |i, ratios| {
+        for (r, n) in ratios.iter_mut().zip(&numerators[i * chunk..]) {
+            r.mul_assign(n);
+        }
+    });
+    Ok(prefix_product(&ratios, worker))
+}
+
+/// The quotient and the remainder returned by [`div_rem`].
+pub type QuotientAndRemainder<F> = (Vec<F>, Vec<F>);
+
+/// Divides the polynomial `num` by `den` (coefficients in ascending order) and
+/// returns the quotient and the remainder, which has fewer coefficients than
+/// `den` without its trailing zeros. The quotient is computed by Newton
+/// iteration on the reversed polynomials, using FFT multiplication. Errors with
+/// `SynthesisError::DivisionByZero` if `den` is zero.
+pub fn div_rem<E: Engine>(
+    num: &[E::Fr],
+    den: &[E::Fr],
+    worker: &Worker,
+) -> Result<QuotientAndRemainder<E::Fr>, SynthesisError> {
+    let b = match den.iter().rposition(|c| !c.is_zero()) {
+        Some(degree) => &den[..=degree],
+        None => return Err(SynthesisError::DivisionByZero),
+    };
+    let a = num;
+    if a.len() < b.len() {
+        return Ok((Vec::new(), a.to_vec()));
+    }
+
+    // The reversed quotient is rev(a) / rev(b) mod x^quotient_len.
+    let quotient_len = a.len() - b.len() + 1;
+    let a_rev = a
+        .iter()
+        .rev()
+        .take(quotient_len)
+        .copied()
+        .collect::<Vec<_>>();
+    let b_rev = b.iter().rev().copied().collect::<Vec<_>>();
+    let mut quotient = poly_mul::<E>(
+        &a_rev,
+        &poly_inverse_mod_xn::<E>(&b_rev, quotient_len, worker)?,
+        worker,
+    )?;
+    quotient.resize(quotient_len, E::Fr::zero());
+    quotient.reverse();
+
+    let qb = poly_mul::<E>(&quotient, b, worker)?;
+    let mut rem = a[..b.len() - 1].to_vec();
+    for (r, qb) in rem.iter_mut().zip(qb.iter()) {
+        r.sub_assign(qb);
+    }
+    Ok((quotient, rem))
+}
+
+// Test multiplying various (low degree) polynomials together and
+// comparing with naive evaluations.
+#[cfg(any(feature = "pairing", features = "blst"))]
+#[test]
+fn polynomial_arith() {
+    use crate::bls::{Bls12, Engine};
+    use rand_core::RngCore;
+
+    fn test_mul<E: ScalarEngine + Engine, R: RngCore>(rng: &mut R) {
+        let worker = Worker::new();
+
+        for coeffs_a in 0..70 {
+            for coeffs_b in 0..70 {
+                let mut a: Vec<_> = (0..coeffs_a)
+                    .map(|_| Scalar::<E>(E::Fr::random(rng)))
+                    .collect();
+                let mut b: Vec<_> = (0..coeffs_b)
+                    .map(|_| Scalar::<E>(E::Fr::random(rng)))
+                    .collect();
+
+                // naive evaluation
+                let mut naive = vec![Scalar(E::Fr::zero()); coeffs_a + coeffs_b];
+                for (i1, a) in a.iter().enumerate() {
+                    for (i2, b) in b.iter().enumerate() {
+                        let mut prod = *a;
+                        prod.group_mul_assign(&b.0);
+                        naive[i1 + i2].group_add_assign(&prod);
+                    }
+                }
+
+                a.resize(coeffs_a + coeffs_b, Scalar(E::Fr::zero()));
+                b.resize(coeffs_a + coeffs_b, Scalar(E::Fr::zero()));
+
+                let mut a = EvaluationDomain::from_coeffs(a).unwrap();
+                let mut b = EvaluationDomain::from_coeffs(b).unwrap();
+
+                a.fft(&worker, &mut None).unwrap();
+                b.fft(&worker, &mut None).unwrap();
+                a.mul_assign(&worker, &b);
+                a.ifft(&worker, &mut None).unwrap();
+
+                for (naive, fft) in naive.iter().zip(a.coeffs.iter()) {
+                    assert!(naive == fft);
+                }
+            }
+        }
+    }
+
+    let rng = &mut rand::thread_rng();
+
+    test_mul::<Bls12, _>(rng);
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn fft_composition() {
+    use crate::bls::{Bls12, Engine};
+    use rand_core::RngCore;
+
+    fn test_comp<E: ScalarEngine + Engine, R: RngCore>(rng: &mut R) {
+        let worker = Worker::new();
+
+        for coeffs in 0..10 {
+            let coeffs = 1 << coeffs;
+
+            let mut v = vec![];
+            for _ in 0..coeffs {
+                v.push(Scalar::<E>(E::Fr::random(rng)));
+            }
+
+            let mut domain = EvaluationDomain::from_coeffs(v.clone()).unwrap();
+            domain.ifft(&worker, &mut None).unwrap();
+            domain.fft(&worker, &mut None).unwrap();
+            assert!(v == domain.coeffs);
+            domain.fft(&worker, &mut None).unwrap();
+            domain.ifft(&worker, &mut None).unwrap();
+            assert!(v == domain.coeffs);
+            domain.icoset_fft(&worker, &mut None).unwrap();
+            domain.coset_fft(&worker, &mut None).unwrap();
+            assert!(v == domain.coeffs);
+            domain.coset_fft(&worker, &mut None).unwrap();
+            domain.icoset_fft(&worker, &mut None).unwrap();
+            assert!(v == domain.coeffs);
+        }
+    }
+
+    let rng = &mut rand::thread_rng();
+
+    test_comp::<Bls12, _>(rng);
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn parallel_fft_consistency() {
+    use crate::bls::{Bls12, Engine};
+    use rand_core::RngCore;
+    use std::cmp::min;
+
+    fn test_consistency<E: ScalarEngine + Engine, R: RngCore>(rng: &mut R) {
+        let worker = Worker::new();
+
+        for _ in 0..5 {
+            for log_d in 0..10 {
+                let d = 1 << log_d;
+
+                let v1 = (0..d)
+                    .map(|_| Scalar::<E>(E::Fr::random(rng)))
+                    .collect::<Vec<_>>();
+                let mut v1 = EvaluationDomain::from_coeffs(v1).unwrap();
+                let mut v2 = EvaluationDomain::from_coeffs(v1.coeffs.clone()).unwrap();
+
+                for log_cpus in log_d..min(log_d + 1, 3) {
+                    parallel_fft(
+                        &mut v1.coeffs,
+                        &worker,
+                        &v1.omega,
+                        log_d,
+                        log_cpus,
+                        None,
+                        None,
+                    )
+                    .unwrap();
+                    serial_fft(&mut v2.coeffs, &v2.omega, log_d);
+
+                    assert!(v1.coeffs == v2.coeffs);
+                }
+            }
+        }
+    }
+
+    let rng = &mut rand::thread_rng();
+
+    test_consistency::<Bls12, _>(rng);
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn parallel_fft_consistency_all_log_cpus() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    for log_d in 1..10 {
+        let coeffs = (0..1 << log_d)
+            .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+            .collect::<Vec<_>>();
+        let domain = EvaluationDomain::from_coeffs(coeffs.clone()).unwrap();
+
+        let mut expected = coeffs.clone();
+        serial_fft(&mut expected, &domain.omega, log_d);
+
+        for log_cpus in 1..=log_d {
+            let mut parallel = coeffs.clone();
+            parallel_fft(
+                &mut parallel,
+                &worker,
+                &domain.omega,
+                log_d,
+                log_cpus,
+                None,
+                None,
+            )
+            .unwrap();
+            assert!(
+                parallel == expected,
+                "log_d = {}, log_cpus = {}",
+                log_d,
+                log_cpus
+            );
+        }
+    }
+}
+
+// `gpu::GPUError` converts into `SynthesisError`, so domain construction and
+// transforms compose with `?` in functions returning `SynthesisError`.
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn synthesis_error_composition() {
+    use crate::bls::{Bls12, Engine};
+    use rand_core::RngCore;
+
+    fn round_trip<E: Engine>(coeffs: Vec<Scalar<E>>) -> Result<Vec<Scalar<E>>, SynthesisError> {
+        let worker = Worker::new();
+
+        let mut domain = EvaluationDomain::from_coeffs(coeffs)?;
+        domain.fft(&worker, &mut None)?;
+        domain.ifft(&worker, &mut None)?;
+
+        Ok(domain.into_coeffs())
+    }
+
+    fn test_round_trip<E: Engine, R: RngCore>(rng: &mut R) {
+        let v = (0..16)
+            .map(|_| Scalar::<E>(E::Fr::random(rng)))
+            .collect::<Vec<_>>();
+
+        assert!(round_trip(v.clone()).unwrap() == v);
+    }
+
+    let rng = &mut rand::thread_rng();
+
+    test_round_trip::<Bls12, _>(rng);
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn from_coeffs_exact() {
+    use crate::bls::{Bls12, Fr};
+
+    let v = vec![Scalar::<Bls12>(Fr::one()); 8];
+    let exact = EvaluationDomain::try_from_coeffs_exact(v.clone()).unwrap();
+    let padded = EvaluationDomain::from_coeffs(v).unwrap();
+    assert_eq!(exact.exp, padded.exp);
+    assert!(exact.omega == padded.omega);
+    assert!(exact.coeffs == padded.coeffs);
+
+    for len in &[0, 3, 5, 12] {
+        let v = vec![Scalar::<Bls12>(Fr::one()); *len];
+        match EvaluationDomain::try_from_coeffs_exact(v) {
+            Err(SynthesisError::InvalidDomainSize) => {}
+            _ => panic!("expected InvalidDomainSize for length {}", len),
+        }
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn sum_of_coeffs() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    for coeffs in 0..70 {
+        let v = (0..coeffs)
+            .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+            .collect::<Vec<_>>();
+
+        let mut expected = Fr::zero();
+        for c in &v {
+            expected.add_assign(&c.0);
+        }
+
+        let domain = EvaluationDomain::from_coeffs(v).unwrap();
+        assert_eq!(domain.sum_of_coeffs(&worker), expected);
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn fft_async() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    let v = (0..64)
+        .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+        .collect::<Vec<_>>();
+
+    let mut expected = EvaluationDomain::from_coeffs(v.clone()).unwrap();
+    expected.fft(&worker, &mut None).unwrap();
+
+    let domain = EvaluationDomain::from_coeffs(v.clone()).unwrap();
+    let (domain, kern) = domain.fft_async(&worker, None).wait().unwrap();
+    assert!(domain.coeffs == expected.coeffs);
+
+    let (domain, _) = domain.ifft_async(&worker, kern).wait().unwrap();
+    assert!(domain.coeffs == v);
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn degree_and_parity() {
+    use crate::bls::{Bls12, Fr};
+
+    let zero = Scalar::<Bls12>(Fr::zero());
+    let one = Scalar::<Bls12>(Fr::one());
+
+    let domain = EvaluationDomain::from_coeffs(vec![zero; 5]).unwrap();
+    assert_eq!(domain.degree(), None);
+    assert!(domain.is_even());
+
+    let domain = EvaluationDomain::from_coeffs(vec![one, zero, one]).unwrap();
+    assert_eq!(domain.degree(), Some(2));
+    assert!(domain.is_even());
+
+    let domain = EvaluationDomain::from_coeffs(vec![one, zero, one, one, zero]).unwrap();
+    assert_eq!(domain.degree(), Some(3));
+    assert!(!domain.is_even());
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn mixed_radix_fft_consistency() {
+    use crate::bls::{Bls12, Fr};
+    use ff::SqrtField;
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    // A primitive cube root of unity, (-1 + sqrt(-3)) / 2
+    let mut minus_three = Fr::from_str("3").unwrap();
+    minus_three.negate();
+    let mut zeta = minus_three.sqrt().unwrap();
+    zeta.sub_assign(&Fr::one());
+    zeta.mul_assign(&Fr::from_str("2").unwrap().inverse().unwrap());
+
+    let twos = 4;
+    let mut root = Fr::root_of_unity();
+    for _ in twos..Fr::S {
+        root.square();
+    }
+    root.mul_assign(&zeta);
+    let subgroup = SmoothSubgroup {
+        root,
+        twos,
+        threes: 1,
+    };
+
+    for coeffs in 1..=48 {
+        let v = (0..coeffs)
+            .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+            .collect::<Vec<_>>();
+
+        let mut domain = MixedRadixDomain::from_coeffs_smooth(v.clone(), &subgroup).unwrap();
+        let m = domain.coeffs.len();
+        assert!(m >= coeffs && m <= 2 * coeffs);
+        let expected = Fr::from_str(&m.to_string()).unwrap();
+        assert_eq!(domain.minv, expected.inverse().unwrap());
+
+        // naive evaluation
+        let mut naive = vec![Fr::zero(); m];
+        for (i, naive) in naive.iter_mut().enumerate() {
+            let point = domain.omega.pow(&[i as u64]);
+            for (j, c) in v.iter().enumerate() {
+                let mut t = point.pow(&[j as u64]);
+                t.mul_assign(&c.0);
+                naive.add_assign(&t);
+            }
+        }
+
+        domain.fft(&worker);
+        assert!(domain.coeffs.iter().map(|c| c.0).collect::<Vec<_>>() == naive);
+
+        domain.ifft(&worker);
+        assert!(domain.coeffs[..coeffs] == v[..]);
+    }
+
+    let v = vec![Scalar::<Bls12>(Fr::one()); 49];
+    assert!(MixedRadixDomain::from_coeffs_smooth(v, &subgroup).is_err());
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn polynomial_mul() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    for coeffs_a in 0..20 {
+        for coeffs_b in 0..20 {
+            let a = (0..coeffs_a)
+                .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+                .collect::<Vec<_>>();
+            let b = (0..coeffs_b)
+                .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+                .collect::<Vec<_>>();
+
+            // naive evaluation
+            let mut naive = vec![Scalar(Fr::zero()); coeffs_a + coeffs_b];
+            for (i1, a) in a.iter().enumerate() {
+                for (i2, b) in b.iter().enumerate() {
+                    let mut prod = *a;
+                    prod.group_mul_assign(&b.0);
+                    naive[i1 + i2].group_add_assign(&prod);
+                }
+            }
+
+            let a = EvaluationDomain::from_coeffs(a).unwrap();
+            let b = EvaluationDomain::from_coeffs(b).unwrap();
+            let a_before = a.coeffs.clone();
+
+            let prod = EvaluationDomain::mul(&a, &b, &worker, &mut None).unwrap();
+            assert!(a.coeffs == a_before);
+            for i in 0..naive.len().max(prod.coeffs.len()) {
+                let zero = Scalar(Fr::zero());
+                assert!(naive.get(i).unwrap_or(&zero) == prod.coeffs.get(i).unwrap_or(&zero));
+            }
+        }
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn fft_cancellation() {
+    use crate::bls::{Bls12, Fr};
+
+    let worker = Worker::new();
+    let cancel = AtomicBool::new(false);
+
+    for log_d in 1..10 {
+        let v = vec![Scalar::<Bls12>(Fr::one()); 1 << log_d];
+
+        let mut domain = EvaluationDomain::from_coeffs(v.clone()).unwrap();
+        let mut expected = EvaluationDomain::from_coeffs(v).unwrap();
+        domain.fft_cancellable(&worker, &mut None, &cancel).unwrap();
+        expected.fft(&worker, &mut None).unwrap();
+        assert!(domain.coeffs == expected.coeffs);
+
+        cancel.store(true, Ordering::Relaxed);
+        match domain.ifft_cancellable(&worker, &mut None, &cancel) {
+            Err(gpu::GPUError::Cancelled) => {}
+            _ => panic!("FFT was not cancelled"),
+        }
+
+        // Each CPU path checks the flag on its own
+        let omega = expected.omega;
+        let a = &mut expected.coeffs;
+        assert!(serial_fft_cancellable(a, &omega, log_d, Some(&cancel), None).is_err());
+        assert!(parallel_fft(a, &worker, &omega, log_d, 1, Some(&cancel), None).is_err());
+        cancel.store(false, Ordering::Relaxed);
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn as_fr() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+
+    let v = (0..13).map(|_| Fr::random(rng)).collect::<Vec<_>>();
+    let domain =
+        EvaluationDomain::<Bls12, _>::from_coeffs(v.iter().map(|f| Scalar(*f)).collect()).unwrap();
+
+    assert_eq!(domain.as_fr().len(), 16);
+    assert_eq!(&domain.as_fr()[..13], &v[..]);
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn naive_mul_consistency() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    for coeffs_a in 0..20 {
+        for coeffs_b in 0..20 {
+            let a = (0..coeffs_a).map(|_| Fr::random(rng)).collect::<Vec<_>>();
+            let b = (0..coeffs_b).map(|_| Fr::random(rng)).collect::<Vec<_>>();
+
+            let len = if coeffs_a == 0 || coeffs_b == 0 {
+                0
+            } else {
+                coeffs_a + coeffs_b - 1
+            };
+            let mut expected = vec![Fr::zero(); len];
+            for (i1, a) in a.iter().enumerate() {
+                for (i2, b) in b.iter().enumerate() {
+                    let mut prod = *a;
+                    prod.mul_assign(b);
+                    expected[i1 + i2].add_assign(&prod);
+                }
+            }
+
+            assert_eq!(naive_mul::<Bls12>(&a, &b, &worker), expected);
+        }
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn extend_from_slice() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    let v = (0..11)
+        .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+        .collect::<Vec<_>>();
+
+    // The extra coefficients go right after the given ones, not after the
+    // padding, whether or not those are a power of two
+    for &(first, second) in &[(4, 11), (3, 5), (3, 11)] {
+        let mut domain = EvaluationDomain::from_coeffs(v[..first].to_vec()).unwrap();
+        domain.extend_from_slice(&v[first..second]).unwrap();
+        assert!(domain.as_ref()[..second] == v[..second]);
+
+        // The domain is re-padded right away
+        let mut expected = EvaluationDomain::from_coeffs(v[..second].to_vec()).unwrap();
+        assert!(domain.as_ref().len().is_power_of_two());
+        assert_eq!(domain.exp, expected.exp);
+        assert!(domain.coeffs == expected.coeffs);
+
+        domain.fft(&worker, &mut None).unwrap();
+        expected.fft(&worker, &mut None).unwrap();
+        assert!(domain.coeffs == expected.coeffs);
+    }
+
+    // Trailing zero coefficients are kept
+    let zero = Scalar::<Bls12>(Fr::zero());
+    let mut domain = EvaluationDomain::from_coeffs(vec![v[0], zero]).unwrap();
+    domain.extend_from_slice(&v[1..2]).unwrap();
+    assert!(domain.as_ref() == [v[0], zero, v[1], zero]);
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn estimate_memory() {
+    use crate::bls::{Bls12, Fr};
+
+    type Domain = EvaluationDomain<Bls12, Scalar<Bls12>>;
+    let fr = std::mem::size_of::<Fr>();
+    let worker = Worker::new();
+
+    assert_eq!(Domain::estimate_memory(0).unwrap(), fr);
+    for log_d in 0..12 {
+        let d = 1usize << log_d;
+        let scratch = match planned_log_cpus(&worker, log_d, None) {
+            0 => 0,
+            _ => d * fr,
+        };
+        assert_eq!(Domain::estimate_memory(d).unwrap(), d * fr + scratch);
+        assert_eq!(
+            Domain::estimate_memory(d / 2 + 1).unwrap(),
+            Domain::estimate_memory(d).unwrap()
+        );
+    }
+    assert!(Domain::estimate_memory(1 << Fr::S).is_err());
+    assert!(Domain::estimate_gpu_memory(1 << Fr::S).is_err());
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn groth16_quotient() {
+    use crate::bls::{Bls12, Fr};
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    fn eval(coeffs: &[Fr], x: &Fr) -> Fr {
+        coeffs.iter().rev().fold(Fr::zero(), |mut acc, c| {
+            acc.mul_assign(x);
+            acc.add_assign(c);
+            acc
+        })
+    }
+
+    let rng = &mut XorShiftRng::from_seed([
+        0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06, 0xbc,
+        0xe5,
+    ]);
+    let worker = Worker::new();
+
+    for log_d in 0..8 {
+        let n = 1 << log_d;
+        let a = (0..n).map(|_| Fr::random(rng)).collect::<Vec<_>>();
+        let b = (0..n).map(|_| Fr::random(rng)).collect::<Vec<_>>();
+        // Make `a·b - c` vanish on the domain.
+        let c = a
+            .iter()
+            .zip(b.iter())
+            .map(|(a, b)| {
+                let mut ab = *a;
+                ab.mul_assign(b);
+                ab
+            })
+            .collect::<Vec<_>>();
+
+        let domain = |v: &[Fr]| {
+            EvaluationDomain::<Bls12, _>::from_coeffs(v.iter().map(|v| Scalar(*v)).collect())
+                .unwrap()
+        };
+        let interpolate = |v: &[Fr]| {
+            let mut poly = domain(v);
+            poly.ifft(&worker, &mut None).unwrap();
+            poly.as_fr().to_vec()
+        };
+
+        let quotient = EvaluationDomain::groth16_quotient(
+            domain(&a),
+            domain(&b),
+            domain(&c),
+            &worker,
+            &mut None,
+        )
+        .unwrap();
+        assert_eq!(quotient.len(), n - 1);
+
+        // a(x)·b(x) - c(x) = h(x)·z(x) at a random point
+        let point = Fr::random(rng);
+        let mut lhs = eval(&interpolate(&a), &point);
+        lhs.mul_assign(&eval(&interpolate(&b), &point));
+        lhs.sub_assign(&eval(&interpolate(&c), &point));
+        let mut rhs = eval(&quotient, &point);
+        rhs.mul_assign(&domain(&a).z(&point));
+        assert_eq!(lhs, rhs);
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn checked_mul_assign() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    for coeffs_a in 0..20 {
+        for coeffs_b in 0..20 {
+            let a = (0..coeffs_a).map(|_| Fr::random(rng)).collect::<Vec<_>>();
+            let b = (0..coeffs_b).map(|_| Fr::random(rng)).collect::<Vec<_>>();
+            let expected = naive_mul::<Bls12>(&a, &b, &worker);
+
+            let mut prod =
+                EvaluationDomain::<Bls12, _>::from_coeffs(a.into_iter().map(Scalar).collect())
+                    .unwrap();
+            prod.checked_mul_assign(&worker, &mut None, &b).unwrap();
+            assert!(prod.as_ref().len() >= expected.len());
+            for (i, c) in prod.as_fr().iter().enumerate() {
+                assert_eq!(*c, expected.get(i).copied().unwrap_or_else(Fr::zero));
+            }
+        }
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn default_worker() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+    assert!(std::ptr::eq(Worker::current(), Worker::current()));
+
+    for log_d in 0..10 {
+        let coeffs = (0..1 << log_d)
+            .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+            .collect::<Vec<_>>();
+        let mut a = EvaluationDomain::from_coeffs(coeffs.clone()).unwrap();
+        let mut b = EvaluationDomain::from_coeffs(coeffs).unwrap();
+
+        a.coset_fft(&worker, &mut None).unwrap();
+        b.coset_fft_default(&mut None).unwrap();
+        assert!(a.coeffs == b.coeffs);
+
+        a.icoset_fft(&worker, &mut None).unwrap();
+        b.icoset_fft_default(&mut None).unwrap();
+        assert!(a.coeffs == b.coeffs);
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn poly_eq() {
+    use crate::bls::{Bls12, Fr};
+
+    let zero = Scalar::<Bls12>(Fr::zero());
+    let one = Scalar::<Bls12>(Fr::one());
+
+    let a = EvaluationDomain::from_coeffs(vec![one, zero, one, one]).unwrap();
+    let b = EvaluationDomain::from_coeffs(vec![one, zero, one, one, zero, zero, zero, zero, zero])
+        .unwrap();
+    assert_eq!(a.as_ref().len(), 4);
+    assert_eq!(b.as_ref().len(), 16);
+    assert!(a.poly_eq(&b));
+    assert!(b.poly_eq(&a));
+
+    let c = EvaluationDomain::from_coeffs(vec![one, zero, one, zero, one]).unwrap();
+    assert!(!a.poly_eq(&c));
+
+    let zero_a = EvaluationDomain::from_coeffs(vec![zero]).unwrap();
+    let zero_b = EvaluationDomain::from_coeffs(vec![zero; 5]).unwrap();
+    assert!(zero_a.poly_eq(&zero_b));
+    assert!(!zero_a.poly_eq(&a));
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn domain_exp_for_max_degree() {
+    use crate::bls::{Bls12, Fr};
+
+    for max_degree in 0..100 {
+        let domain =
+            EvaluationDomain::<Bls12, _>::from_coeffs(vec![Scalar(Fr::one()); max_degree + 1])
+                .unwrap();
+        assert_eq!(
+            domain_exp_for_degree::<Bls12>(max_degree).unwrap(),
+            domain.exp
+        );
+    }
+    assert!(domain_exp_for_degree::<Bls12>(usize::MAX).is_err());
+    assert!(domain_exp_for_degree::<Bls12>(1 << Fr::S).is_err());
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn apply_mut() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    for log_d in 0..10 {
+        let coeffs = (0..1 << log_d)
+            .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+            .collect::<Vec<_>>();
+        let mut a = EvaluationDomain::from_coeffs(coeffs.clone()).unwrap();
+        let mut b = EvaluationDomain::from_coeffs(coeffs).unwrap();
+
+        // `distribute_powers` expressed through `apply_mut`
+        let g = Fr::multiplicative_generator();
+        a.distribute_powers(&worker, g);
+        b.apply_mut(&worker, |i, v| v.group_mul_assign(&g.pow(&[i as u64])));
+        assert!(a.coeffs == b.coeffs);
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn evaluate_multi() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    for &(coeffs, points) in &[(0, 5), (1, 0), (7, 3), (40, 40), (100, 300), (300, 100)] {
+        let poly = EvaluationDomain::from_coeffs(
+            (0..coeffs)
+                .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+                .collect(),
+        )
+        .unwrap();
+        let points = (0..points).map(|_| Fr::random(rng)).collect::<Vec<_>>();
+
+        let expected = points.iter().map(|p| poly.evaluate(p)).collect::<Vec<_>>();
+        assert_eq!(poly.evaluate_multi(&points, &worker).unwrap(), expected);
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn vanishing_poly_coeffs() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+
+    for len in 1..20 {
+        let domain =
+            EvaluationDomain::<Bls12, _>::from_coeffs(vec![Scalar(Fr::zero()); len]).unwrap();
+        let coeffs = domain.vanishing_poly_coeffs();
+        assert_eq!(coeffs.len(), domain.as_ref().len() + 1);
+
+        let tau = Fr::random(rng);
+        assert_eq!(horner::<Bls12>(&coeffs, &tau), domain.z(&tau));
+        // The polynomial vanishes on the domain
+        assert_eq!(horner::<Bls12>(&coeffs, &domain.omega), Fr::zero());
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn reduce_mod_vanishing() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    for log_d in 0..6 {
+        let a = (0..1 << log_d)
+            .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+            .collect::<Vec<_>>();
+        let b = (0..1 << log_d)
+            .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+            .collect::<Vec<_>>();
+        let a = EvaluationDomain::from_coeffs(a).unwrap();
+        let b = EvaluationDomain::from_coeffs(b).unwrap();
+
+        // The cyclic product, computed by multiplying in the small domain
+        let mut expected = EvaluationDomain::from_coeffs(a.coeffs.clone()).unwrap();
+        let mut b_evals = EvaluationDomain::from_coeffs(b.coeffs.clone()).unwrap();
+        expected.fft(&worker, &mut None).unwrap();
+        b_evals.fft(&worker, &mut None).unwrap();
+        expected.mul_assign(&worker, &b_evals);
+        expected.ifft(&worker, &mut None).unwrap();
+
+        // The full product, reduced into the small domain
+        let mut prod = EvaluationDomain::mul(&a, &b, &worker, &mut None).unwrap();
+        assert!(prod.reduce_mod_vanishing(prod.exp + 1).is_err());
+        prod.reduce_mod_vanishing(a.exp).unwrap();
+        assert!(prod.coeffs == expected.coeffs);
+        assert_eq!(prod.omega, a.omega);
+        assert_eq!(prod.minv, a.minv);
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn thread_safety() {
+    use crate::bls::{Bls12, Fr};
+    use std::sync::Arc;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+    fn assert_domain_send_sync<E: ScalarEngine, G: Group<E>>() {
+        assert_send_sync::<EvaluationDomain<E, G>>();
+    }
+    assert_domain_send_sync::<Bls12, Scalar<Bls12>>();
+
+    let rng = &mut rand::thread_rng();
+    let domain = Arc::new(
+        EvaluationDomain::from_coeffs((0..100).map(|_| Scalar::<Bls12>(Fr::random(rng))).collect())
+            .unwrap(),
+    );
+    let points = (0..8).map(|_| Fr::random(rng)).collect::<Vec<_>>();
+
+    let handles = points
+        .iter()
+        .map(|point| {
+            let domain = domain.clone();
+            let point = *point;
+            std::thread::spawn(move || domain.evaluate(&point))
+        })
+        .collect::<Vec<_>>();
+    for (handle, point) in handles.into_iter().zip(points.iter()) {
+        assert_eq!(handle.join().unwrap(), domain.evaluate(point));
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn coset_fft_into() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    for log_d in 0..10 {
+        let coeffs = (0..1 << log_d)
+            .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+            .collect::<Vec<_>>();
+        let domain = EvaluationDomain::from_coeffs(coeffs.clone()).unwrap();
+        let mut expected = EvaluationDomain::from_coeffs(coeffs).unwrap();
+        expected.coset_fft(&worker, &mut None).unwrap();
+
+        let mut out = vec![Fr::zero(); 1 << log_d];
+        domain.coset_fft_into(&mut out, &worker, &mut None).unwrap();
+        assert_eq!(out, expected.as_fr());
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn planned_parallelism() {
+    use crate::bls::{Bls12, Fr};
+
+    let worker = Worker::new();
+    let log_cpus = worker.log_num_cpus();
+
+    for log_d in 0..12 {
+        let domain =
+            EvaluationDomain::<Bls12, _>::from_coeffs(vec![Scalar(Fr::one()); 1 << log_d]).unwrap();
+        let expected = if log_d <= log_cpus || log_cpus == 0 {
+            0
+        } else {
+            log_cpus
+        };
+        assert_eq!(domain.planned_parallelism(&worker), expected);
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn cloned_coset_fft() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    for log_d in 0..10 {
+        let coeffs = (0..1 << log_d)
+            .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+            .collect::<Vec<_>>();
+        let domain = EvaluationDomain::from_coeffs(coeffs.clone()).unwrap();
+        let mut expected = EvaluationDomain::from_coeffs(coeffs.clone()).unwrap();
+        expected.coset_fft(&worker, &mut None).unwrap();
+
+        let evals = domain.cloned_coset_fft(&worker, &mut None).unwrap();
+        assert!(evals == expected.coeffs);
+        assert!(domain.coeffs == coeffs);
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn partial_fft() {
+    use crate::bls::{Bls12, Fr};
+    use rand_core::RngCore;
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    for log_d in 0..10 {
+        let d = 1 << log_d;
+        let coeffs = (0..d)
+            .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+            .collect::<Vec<_>>();
+        let domain = EvaluationDomain::from_coeffs(coeffs.clone()).unwrap();
+        let mut evals = EvaluationDomain::from_coeffs(coeffs).unwrap();
+        evals.fft(&worker, &mut None).unwrap();
+
+        // Both the direct and the full-transform paths
+        for &count in &[0, 1, 3, 2 * log_d + 1] {
+            let indices = (0..count)
+                .map(|_| rng.next_u32() as usize % d)
+                .collect::<Vec<_>>();
+            let expected = indices
+                .iter()
+                .map(|i| evals.as_fr()[*i])
+                .collect::<Vec<_>>();
+            assert_eq!(domain.partial_fft(&indices, &worker), expected);
+        }
+    }
+}
+
+#[cfg(all(feature = "scratch-pool", any(feature = "pairing", feature = "blst")))]
+#[test]
+fn scratch_pool() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+    let log_n = 6;
+
+    // Repeated transforms reuse scratch space, with identical results
+    for _ in 0..3 {
+        let coeffs = (0..1 << log_n)
+            .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+            .collect::<Vec<_>>();
+        let mut serial = coeffs.clone();
+        let mut parallel = coeffs;
+
+        serial_fft(
+            &mut serial,
+            &Fr::root_of_unity().pow(&[1 << (Fr::S - log_n)]),
+            log_n,
+        );
+        parallel_fft(
+            &mut parallel,
+            &worker,
+            &Fr::root_of_unity().pow(&[1 << (Fr::S - log_n)]),
+            log_n,
+            2,
+            None,
+            None,
+        )
+        .unwrap();
+        assert!(serial == parallel);
+
+        let key = (
+            size_of::<Scalar<Bls12>>(),
+            align_of::<Scalar<Bls12>>(),
+            4,
+            1 << (log_n - 2),
+        );
+        assert!(SCRATCH_POOL.with(|pool| pool.borrow().contains_key(&key)));
+    }
+
+    clear_scratch_pool();
+    assert_eq!(SCRATCH_POOL.with(|pool| pool.borrow().len()), 0);
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn from_coeffs_padded_to() {
+    use crate::bls::{Bls12, Fr};
+
+    let one = Scalar::<Bls12>(Fr::one());
+
+    for len in 0..20 {
+        for min_len in 0..20 {
+            let domain = EvaluationDomain::from_coeffs_padded_to(vec![one; len], min_len).unwrap();
+            let size = domain.as_ref().len();
+            assert!(size.is_power_of_two());
+            assert!(size >= len && size >= min_len);
+            assert!(size == 1 || size / 2 < len.max(min_len));
+            assert_eq!(domain.degree(), len.checked_sub(1));
+        }
+    }
+    assert!(EvaluationDomain::from_coeffs_padded_to(vec![one], 1 << Fr::S).is_err());
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn coset_quotient() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    for log_d in 1..8 {
+        let d = 1 << log_d;
+        // `a·b` vanishes on the domain
+        let a = (0..d)
+            .map(|i| {
+                Scalar::<Bls12>(if i % 2 == 0 {
+                    Fr::random(rng)
+                } else {
+                    Fr::zero()
+                })
+            })
+            .collect::<Vec<_>>();
+        let b = (0..d)
+            .map(|i| {
+                Scalar::<Bls12>(if i % 2 == 1 {
+                    Fr::random(rng)
+                } else {
+                    Fr::zero()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let expected = EvaluationDomain::groth16_quotient(
+            EvaluationDomain::from_coeffs(a.clone()).unwrap(),
+            EvaluationDomain::from_coeffs(b.clone()).unwrap(),
+            EvaluationDomain::from_coeffs(vec![Scalar(Fr::zero()); d]).unwrap(),
+            &worker,
+            &mut None,
+        )
+        .unwrap();
+
+        let mut a = EvaluationDomain::from_coeffs(a).unwrap();
+        a.ifft(&worker, &mut None).unwrap();
+        let mut b = EvaluationDomain::from_coeffs(b).unwrap();
+        b.ifft(&worker, &mut None).unwrap();
+        b.coset_fft(&worker, &mut None).unwrap();
+
+        a.coset_quotient(&worker, &mut None, &b).unwrap();
+        assert_eq!(&a.as_fr()[..d - 1], &expected[..]);
+        assert_eq!(a.as_fr()[d - 1], Fr::zero());
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn serial_fft_small_domains() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+
+    // Size 1: the identity
+    let x = Fr::random(rng);
+    let mut a = [Scalar::<Bls12>(x)];
+    serial_fft(&mut a, &Fr::one(), 0);
+    assert_eq!(a[0].0, x);
+
+    // Size 2: omega = -1, so the result is [x + y, x - y]
+    let y = Fr::random(rng);
+    let mut minus_one = Fr::one();
+    minus_one.negate();
+    let mut a = [Scalar::<Bls12>(x), Scalar(y)];
+    serial_fft(&mut a, &minus_one, 1);
+    let mut sum = x;
+    sum.add_assign(&y);
+    let mut diff = x;
+    diff.sub_assign(&y);
+    assert_eq!(a[0].0, sum);
+    assert_eq!(a[1].0, diff);
+
+    // Both agree with the domain transforms
+    for len in 1..=2 {
+        let coeffs = (0..len)
+            .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+            .collect::<Vec<_>>();
+        let mut domain = EvaluationDomain::from_coeffs(coeffs.clone()).unwrap();
+        domain.fft(&Worker::new(), &mut None).unwrap();
+        domain.ifft(&Worker::new(), &mut None).unwrap();
+        assert!(domain.coeffs == coeffs);
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn inner_product() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    for log_d in 0..10 {
+        let a = (0..1 << log_d)
+            .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+            .collect::<Vec<_>>();
+        let b = (0..1 << log_d)
+            .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+            .collect::<Vec<_>>();
+
+        let mut expected = Fr::zero();
+        for (a, b) in a.iter().zip(b.iter()) {
+            let mut tmp = a.0;
+            tmp.mul_assign(&b.0);
+            expected.add_assign(&tmp);
+        }
+
+        let a = EvaluationDomain::from_coeffs(a).unwrap();
+        let b = EvaluationDomain::from_coeffs(b).unwrap();
+        assert_eq!(a.inner_product(&b, &worker), expected);
+        assert_eq!(b.inner_product(&a, &worker), expected);
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn parallel_fft_log_cpus_boundary() {
+    use crate::bls::{Bls12, Fr};
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    let rng = &mut XorShiftRng::seed_from_u64(0x5eed);
+    let worker = Worker::new();
+    let machine_log_cpus = worker.log_num_cpus();
+
+    let mut log_cpus_values = (0..5).collect::<Vec<u32>>();
+    log_cpus_values.push(machine_log_cpus);
+
+    for log_cpus in log_cpus_values {
+        for log_n in log_cpus.saturating_sub(1)..=log_cpus + 1 {
+            let omega = Fr::root_of_unity().pow(&[1 << (Fr::S - log_n)]);
+            let coeffs = (0..1 << log_n)
+                .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+                .collect::<Vec<_>>();
+
+            let mut expected = coeffs.clone();
+            serial_fft(&mut expected, &omega, log_n);
+
+            if log_n >= log_cpus {
+                let mut parallel = coeffs.clone();
+                parallel_fft(&mut parallel, &worker, &omega, log_n, log_cpus, None, None).unwrap();
+                assert!(
+                    parallel == expected,
+                    "log_n = {}, log_cpus = {}",
+                    log_n,
+                    log_cpus
+                );
+            }
+
+            let mut best = coeffs;
+            cpu_fft(
+                &mut best,
+                &worker,
+                &omega,
+                log_n,
+                None,
+                CpuFftOptions::default(),
+            )
+            .unwrap();
+            assert!(best == expected, "log_n = {}", log_n);
+        }
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn concat() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+
+    for log_d in 0..8 {
+        let a = (0..1 << log_d)
+            .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+            .collect::<Vec<_>>();
+        let b = (0..1 << log_d)
+            .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+            .collect::<Vec<_>>();
+
+        let joined = EvaluationDomain::from_coeffs(a.clone())
+            .unwrap()
+            .concat(EvaluationDomain::from_coeffs(b.clone()).unwrap())
+            .unwrap();
+        let expected = EvaluationDomain::from_coeffs([a, b].concat()).unwrap();
+        assert!(joined.coeffs == expected.coeffs);
+        assert_eq!(joined.exp, log_d + 1);
+        assert_eq!(joined.omega, expected.omega);
+        assert_eq!(joined.minv, expected.minv);
+    }
+
+    let one = Scalar::<Bls12>(Fr::one());
+    let a = EvaluationDomain::from_coeffs(vec![one; 2]).unwrap();
+    let b = EvaluationDomain::from_coeffs(vec![one; 4]).unwrap();
+    assert!(a.concat(b).is_err());
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn kernel_cache() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+    let mut cache = KernelCache::<Bls12>::new(false);
+    assert_eq!(cache.log_d(), None);
+
+    for &(log_d, cached) in &[(4, 4), (2, 4), (6, 6), (5, 6), (6, 6)] {
+        let coeffs = (0..1 << log_d)
+            .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+            .collect::<Vec<_>>();
+        let mut domain = EvaluationDomain::from_coeffs(coeffs.clone()).unwrap();
+
+        domain.fft(&worker, cache.get_or_create(log_d)).unwrap();
+        domain.ifft(&worker, cache.get_or_create(log_d)).unwrap();
+        assert!(domain.coeffs == coeffs);
+        assert_eq!(cache.log_d(), Some(cached));
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn assert_degree_below() {
+    use crate::bls::{Bls12, Fr};
+
+    let zero = Scalar::<Bls12>(Fr::zero());
+    let one = Scalar::<Bls12>(Fr::one());
+
+    let domain = EvaluationDomain::from_coeffs(vec![one, zero, one]).unwrap();
+    assert!(domain.assert_degree_below(0).is_err());
+    assert!(domain.assert_degree_below(2).is_err());
+    assert!(domain.assert_degree_below(3).is_ok());
+    assert!(domain.assert_degree_below(100).is_ok());
+
+    let domain = EvaluationDomain::from_coeffs(vec![zero; 4]).unwrap();
+    assert!(domain.assert_degree_below(0).is_ok());
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn update_evaluation() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    for log_d in 0..8 {
+        let d = 1 << log_d;
+        let mut coeffs = (0..d)
+            .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+            .collect::<Vec<_>>();
+        let mut evals = EvaluationDomain::from_coeffs(coeffs.clone()).unwrap();
+        evals.fft(&worker, &mut None).unwrap();
+
+        for coeff_index in (0..d).step_by(3) {
+            let delta = Fr::random(rng);
+            coeffs[coeff_index].0.add_assign(&delta);
+            evals.update_evaluation(coeff_index, delta, &worker);
+        }
+
+        let mut expected = EvaluationDomain::from_coeffs(coeffs).unwrap();
+        expected.fft(&worker, &mut None).unwrap();
+        assert!(evals.coeffs == expected.coeffs);
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn form_tracking() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    let coeffs = (0..16)
+        .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+        .collect::<Vec<_>>();
+    let new_domain = || EvaluationDomain::from_coeffs(coeffs.clone()).unwrap();
+
+    let mut domain = new_domain();
+    assert_eq!(domain.form(), Form::Coefficients);
+    domain.fft(&worker, &mut None).unwrap();
+    assert_eq!(domain.form(), Form::Evaluations);
+    assert!(matches!(
+        domain.try_into_coeffs(),
+        Err(SynthesisError::InvalidDomainForm)
+    ));
+
+    let mut domain = new_domain();
+    domain.coset_fft(&worker, &mut None).unwrap();
+    assert_eq!(domain.form(), Form::Evaluations);
+    domain.icoset_fft(&worker, &mut None).unwrap();
+    assert_eq!(domain.form(), Form::Coefficients);
+    assert!(domain.try_into_coeffs().unwrap() == coeffs);
+
+    // Methods on coefficients reject evaluations, and rebuilt domains keep
+    // their form
+    let mut domain = new_domain();
+    domain.fft(&worker, &mut None).unwrap();
+    assert!(matches!(
+        domain.reduce_mod_vanishing(2),
+        Err(SynthesisError::InvalidDomainForm)
+    ));
+    assert!(matches!(
+        domain.trim_trailing_zeros(),
+        Err(SynthesisError::InvalidDomainForm)
+    ));
+    assert_eq!(domain.form(), Form::Evaluations);
+    let joined = domain.concat(new_domain()).unwrap();
+    assert_eq!(joined.form(), Form::Evaluations);
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn extend() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    let first = (0..3).map(|_| Fr::random(rng)).collect::<Vec<_>>();
+    let more = (0..5).map(|_| Fr::random(rng)).collect::<Vec<_>>();
+    let last = (0..2).map(|_| Fr::random(rng)).collect::<Vec<_>>();
+
+    let mut domain =
+        EvaluationDomain::<Bls12, _>::from_coeffs(first.iter().copied().map(Scalar).collect())
+            .unwrap();
+    domain.extend(more.iter().copied());
+    domain.extend(last.iter().copied());
+    // Padding is deferred, and `more` replaces the zero `from_coeffs` padded
+    // `first` with
+    assert_eq!(domain.as_ref().len(), 3 + 5 + 2);
+    assert_eq!(domain.exp, 2);
+    let mut expected = [&first[..], &more[..], &last[..]].concat();
+    assert_eq!(domain.as_fr(), &expected[..]);
+    expected.resize(16, Fr::zero());
+    let mut expected =
+        EvaluationDomain::<Bls12, _>::from_coeffs(expected.into_iter().map(Scalar).collect())
+            .unwrap();
+
+    domain.fft(&worker, &mut None).unwrap();
+    expected.fft(&worker, &mut None).unwrap();
+    assert!(domain.coeffs == expected.coeffs);
+    assert_eq!(domain.exp, 4);
+    assert_eq!(domain.omega, expected.omega);
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn extend_then_use() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    let coeffs = (0..11)
+        .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+        .collect::<Vec<_>>();
+    let extended = || {
+        let mut domain = EvaluationDomain::from_coeffs(coeffs[..3].to_vec()).unwrap();
+        domain.extend(coeffs[3..].iter().map(|c| c.0));
+        domain
+    };
+    let padded = || EvaluationDomain::<Bls12, _>::from_coeffs(coeffs.clone()).unwrap();
+    assert_eq!(extended().as_ref().len(), 11);
+
+    // Methods taking `&self` work on the padded domain
+    let evals = extended().cloned_coset_fft(&worker, &mut None).unwrap();
+    assert!(evals == padded().cloned_coset_fft(&worker, &mut None).unwrap());
+    for k in 0..16 {
+        assert_eq!(
+            extended().eval_at_root(k, &worker).unwrap(),
+            padded().eval_at_root(k, &worker).unwrap()
+        );
+    }
+
+    // Methods taking `self` pad it first
+    let expected = padded().concat(padded()).unwrap();
+    for joined in vec![
+        extended().concat(extended()).unwrap(),
+        extended().concat(padded()).unwrap(),
+        padded().concat(extended()).unwrap(),
+    ] {
+        assert_eq!(joined.exp, 5);
+        assert!(joined.coeffs == expected.coeffs);
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn mul_assign_resized() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    for &(len_a, len_b) in &[(1usize, 1usize), (2, 8), (8, 2), (3, 5), (16, 4), (4, 16)] {
+        let a = (0..len_a).map(|_| Fr::random(rng)).collect::<Vec<_>>();
+        let b = (0..len_b).map(|_| Fr::random(rng)).collect::<Vec<_>>();
+        let expected = naive_mul::<Bls12>(&a, &b, &worker);
+
+        // Domains large enough for the product when combined
+        let size = (len_a + len_b).next_power_of_two();
+        let domain = |v: &[Fr], other_len: usize| {
+            let len = if v.len() >= other_len { size } else { v.len() };
+            let mut d = EvaluationDomain::<Bls12, _>::from_coeffs_padded_to(
+                v.iter().copied().map(Scalar).collect(),
+                len,
+            )
+            .unwrap();
+            d.fft(&worker, &mut None).unwrap();
+            d
+        };
+        let mut prod = domain(&a, len_b);
+        prod.mul_assign_resized(&worker, &mut None, &domain(&b, len_a))
+            .unwrap();
+        prod.ifft(&worker, &mut None).unwrap();
+
+        assert_eq!(prod.as_ref().len(), size);
+        for (i, c) in prod.as_fr().iter().enumerate() {
+            assert_eq!(*c, expected.get(i).copied().unwrap_or_else(Fr::zero));
+        }
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn precomputed_twiddles() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    for log_d in 0..10 {
+        let coeffs = (0..1 << log_d)
+            .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+            .collect::<Vec<_>>();
+        let mut expected = EvaluationDomain::from_coeffs(coeffs.clone()).unwrap();
+        let mut domain = EvaluationDomain::from_coeffs(coeffs.clone()).unwrap();
+        domain.precompute_twiddles(&worker);
+
+        expected.fft(&worker, &mut None).unwrap();
+        domain.fft(&worker, &mut None).unwrap();
+        assert!(domain.coeffs == expected.coeffs);
+        domain.ifft(&worker, &mut None).unwrap();
+        assert!(domain.coeffs == coeffs);
+
+        // Both directions of the parallel FFT, whose sub-FFTs use the table
+        // of the full domain
+        let powers = twiddle_table(&expected.omega, log_d, &worker);
+        for log_cpus in 0..=log_d.min(3) {
+            for &inverse in &[false, true] {
+                let omega = if inverse {
+                    expected.omegainv
+                } else {
+                    expected.omega
+                };
+                let mut serial = coeffs.clone();
+                serial_fft(&mut serial, &omega, log_d);
+                let mut parallel = coeffs.clone();
+                let twiddles = Twiddles {
+                    powers: &powers,
+                    inverse,
+                };
+                parallel_fft(
+                    &mut parallel,
+                    &worker,
+                    &omega,
+                    log_d,
+                    log_cpus,
+                    None,
+                    Some(twiddles),
+                )
+                .unwrap();
+                assert!(serial == parallel);
+            }
+        }
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn rotate() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    for log_d in 0..6 {
+        let d = 1 << log_d;
+        let coeffs = (0..d).map(|_| Fr::random(rng)).collect::<Vec<_>>();
+        let mut domain =
+            EvaluationDomain::<Bls12, _>::from_coeffs(coeffs.iter().cloned().map(Scalar).collect())
+                .unwrap();
+        domain.fft(&worker, &mut None).unwrap();
+
+        for &k in &[0isize, 1, -1, 3, -3, d as isize + 2] {
+            let rotated = domain.rotate(k, &worker).unwrap();
+            assert_eq!(rotated.form(), Form::Evaluations);
+            for i in 0..d {
+                let point = domain
+                    .omega
+                    .pow(&[(i as isize + k).rem_euclid(d as isize) as u64]);
+                assert!(rotated.as_fr()[i] == horner::<Bls12>(&coeffs, &point));
+            }
+        }
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn omega_order() {
+    use crate::bls::{Bls12, Fr};
+
+    for exp in 0..12 {
+        let domain =
+            EvaluationDomain::<Bls12, _>::from_coeffs(vec![Scalar(Fr::zero()); 1 << exp]).unwrap();
+        assert_eq!(domain.exp, exp);
+
+        // omega^(2^exp) is one, and no smaller power of two of it is
+        let mut power = domain.omega;
+        for _ in 0..exp {
+            assert!(power != Fr::one());
+            power.square();
+        }
+        assert!(power == Fr::one());
+
+        let mut inverse = domain.omega;
+        inverse.mul_assign(&domain.omegainv);
+        assert!(inverse == Fr::one());
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn align_with() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    let coeffs = (0..5)
+        .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+        .collect::<Vec<_>>();
+    let mut large = EvaluationDomain::from_coeffs(vec![Scalar(Fr::one()); 32]).unwrap();
+    large.fft(&worker, &mut None).unwrap();
+    let small = EvaluationDomain::from_coeffs(vec![Scalar(Fr::one()); 8]).unwrap();
+
+    // Growing, from either form
+    let mut expected = EvaluationDomain::from_coeffs_padded_to(coeffs.clone(), 32).unwrap();
+    expected.fft(&worker, &mut None).unwrap();
+    let mut domain = EvaluationDomain::from_coeffs(coeffs.clone()).unwrap();
+    domain.align_with(&large, &worker, &mut None).unwrap();
+    assert_eq!(domain.form(), Form::Evaluations);
+    assert!(domain.coeffs == expected.coeffs);
+
+    let mut domain = EvaluationDomain::from_coeffs(coeffs.clone()).unwrap();
+    domain.fft(&worker, &mut None).unwrap();
+    domain.align_with(&large, &worker, &mut None).unwrap();
+    assert!(domain.coeffs == expected.coeffs);
+
+    // Shrinking back into coefficient form
+    domain.align_with(&small, &worker, &mut None).unwrap();
+    assert_eq!(domain.form(), Form::Coefficients);
+    assert_eq!(domain.coeffs.len(), 8);
+    assert!(domain.coeffs[..5] == coeffs[..]);
+
+    // The polynomial doesn't fit into a domain of size 4
+    let tiny = EvaluationDomain::from_coeffs(vec![Scalar(Fr::one()); 4]).unwrap();
+    assert!(domain.align_with(&tiny, &worker, &mut None).is_err());
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn for_each_coeff_bytes() {
+    use crate::bls::{Bls12, Fr};
+    use ff::PrimeFieldRepr;
+
+    let rng = &mut rand::thread_rng();
+
+    let coeffs = (0..5).map(|_| Fr::random(rng)).collect::<Vec<_>>();
+    let mut expected = Vec::new();
+    for coeff in coeffs.iter().chain(std::iter::repeat(&Fr::zero()).take(3)) {
+        coeff.into_repr().write_le(&mut expected).unwrap();
+    }
+
+    let bytes_of = |domain: &EvaluationDomain<Bls12, Scalar<Bls12>>| {
+        let mut bytes = Vec::new();
+        domain.for_each_coeff_bytes(|chunk| bytes.extend_from_slice(chunk));
+        bytes
+    };
+
+    let domain =
+        EvaluationDomain::<Bls12, _>::from_coeffs(coeffs.iter().cloned().map(Scalar).collect())
+            .unwrap();
+    assert_eq!(bytes_of(&domain), expected);
+
+    // The padding is included before `extend` applies it
+    let mut extended = EvaluationDomain::<Bls12, _>::from_coeffs(vec![Scalar(coeffs[0])]).unwrap();
+    extended.extend(coeffs[1..].iter().cloned());
+    assert_eq!(bytes_of(&extended), expected);
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn coeffs_to_bytes_par() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    for len in 1..40 {
+        let coeffs = (0..len).map(|_| Fr::random(rng)).collect::<Vec<_>>();
+        let mut domain =
+            EvaluationDomain::<Bls12, _>::from_coeffs(vec![Scalar(coeffs[0])]).unwrap();
+        domain.extend(coeffs[1..].iter().cloned());
+
+        let mut expected = Vec::new();
+        domain.for_each_coeff_bytes(|chunk| expected.extend_from_slice(chunk));
+        assert_eq!(domain.coeffs_to_bytes_par(&worker), expected);
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn chunk_tuning() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    let a = (0..1 << 10)
+        .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+        .collect::<Vec<_>>();
+    let b = (0..1 << 10)
+        .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+        .collect::<Vec<_>>();
+    let other = EvaluationDomain::from_coeffs(b).unwrap();
+
+    let mut expected = EvaluationDomain::from_coeffs(a.clone()).unwrap();
+    expected.mul_assign(&worker, &other);
+
+    // Every call while tuning, and the ones after, compute the same product
+    set_chunk_tuning(true);
+    for _ in 0..CHUNK_TUNING_CANDIDATES.len() + 2 {
+        let mut domain = EvaluationDomain::from_coeffs(a.clone()).unwrap();
+        domain.mul_assign(&worker, &other);
+        assert!(domain.coeffs == expected.coeffs);
+    }
+    set_chunk_tuning(false);
+
+    let best = CHUNK_TUNING.lock().unwrap()[&(DomainOp::MulAssign, 10)].best;
+    assert!(CHUNK_TUNING_CANDIDATES.contains(&best.unwrap()));
+    let cached = chunk_tuning_slot((DomainOp::MulAssign, 10)).load(Ordering::Relaxed);
+    assert_eq!(best, Some(cached));
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn swap_coeffs() {
+    use crate::bls::{Bls12, Fr};
+
+    let worker = Worker::new();
+
+    let mut a = EvaluationDomain::<Bls12, _>::from_coeffs(vec![Scalar(Fr::one()); 4]).unwrap();
+    let mut b = EvaluationDomain::<Bls12, _>::from_coeffs(vec![Scalar(Fr::zero()); 3]).unwrap();
+    b.fft(&worker, &mut None).unwrap();
+    let ptr = a.coeffs.as_ptr();
+
+    a.swap_coeffs(&mut b);
+    assert!(a.as_fr() == [Fr::zero(); 4]);
+    assert_eq!(a.form(), Form::Evaluations);
+    assert!(b.as_fr() == [Fr::one(); 4]);
+    assert_eq!(b.form(), Form::Coefficients);
+    assert_eq!(b.coeffs.as_ptr(), ptr);
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+#[should_panic]
+fn swap_coeffs_of_different_sizes() {
+    use crate::bls::{Bls12, Fr};
+
+    let mut a = EvaluationDomain::<Bls12, _>::from_coeffs(vec![Scalar(Fr::one()); 4]).unwrap();
+    let mut b = EvaluationDomain::<Bls12, _>::from_coeffs(vec![Scalar(Fr::one()); 8]).unwrap();
+    a.swap_coeffs(&mut b);
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn fft_sparse() {
+    use crate::bls::{Bls12, Fr};
+    use rand_core::RngCore;
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    for log_d in 0..11 {
+        let d = 1 << log_d;
+        // From all zeros to dense enough to fall back to the FFT
+        for nnz in 0..=log_d / 2 + 1 {
+            let mut coeffs = vec![Scalar::<Bls12>(Fr::zero()); d];
+            for _ in 0..nnz {
+                coeffs[rng.next_u32() as usize % d] = Scalar(Fr::random(rng));
+            }
+
+            let mut expected = EvaluationDomain::from_coeffs(coeffs.clone()).unwrap();
+            expected.fft(&worker, &mut None).unwrap();
+            let mut domain = EvaluationDomain::from_coeffs(coeffs).unwrap();
+            domain.fft_sparse(&worker);
+            assert_eq!(domain.form(), Form::Evaluations);
+            assert!(domain.coeffs == expected.coeffs);
+        }
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn coeffs_mut_in_coefficient_form() {
+    use crate::bls::{Bls12, Fr};
+
+    let worker = Worker::new();
+
+    let mut domain = EvaluationDomain::<Bls12, _>::from_coeffs(vec![Scalar(Fr::one())]).unwrap();
+    domain.extend(vec![Fr::one(); 2]);
+    {
+        let coeffs = domain.coeffs_mut_in_coefficient_form().unwrap();
+        assert_eq!(coeffs.len(), 4);
+        coeffs[3] = Scalar(Fr::one());
+    }
+    assert!(domain.as_fr() == [Fr::one(); 4]);
+
+    domain.fft(&worker, &mut None).unwrap();
+    assert!(matches!(
+        domain.coeffs_mut_in_coefficient_form(),
+        Err(SynthesisError::InvalidDomainForm)
+    ));
+    domain.ifft(&worker, &mut None).unwrap();
+    assert!(domain.coeffs_mut_in_coefficient_form().is_ok());
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn roots_of_unity() {
+    use crate::bls::{Bls12, Fr};
+
+    let roots = RootsOfUnity::<Bls12>::get();
+    assert!(Arc::ptr_eq(&roots, &RootsOfUnity::<Bls12>::get()));
+
+    let mut omega = Fr::root_of_unity();
+    for exp in (0..=Fr::S).rev() {
+        assert!(roots.omega_for_exp(exp) == omega);
+        omega.square();
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn try_from_coeffs() {
+    use crate::bls::{Bls12, Fr};
+
+    for len in 0..20 {
+        let coeffs = (0..len)
+            .map(|i| Scalar::<Bls12>(Fr::from_str(&i.to_string()).unwrap()))
+            .collect::<Vec<_>>();
+        let expected = EvaluationDomain::from_coeffs(coeffs.clone()).unwrap();
+        let domain = EvaluationDomain::try_from_coeffs(coeffs).unwrap();
+        assert_eq!(domain.exp, expected.exp);
+        assert!(domain.coeffs == expected.coeffs);
+    }
+
+    assert!(matches!(
+        try_resize(
+            vec![Scalar::<Bls12>(Fr::one())],
+            usize::MAX / 2,
+            Scalar(Fr::zero())
+        ),
+        Err(SynthesisError::OutOfMemory)
+    ));
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn add_assign() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    let a = (0..100).map(|_| Fr::random(rng)).collect::<Vec<_>>();
+    let b = (0..100).map(|_| Fr::random(rng)).collect::<Vec<_>>();
+    let mut domain =
+        EvaluationDomain::<Bls12, _>::from_coeffs(a.iter().cloned().map(Scalar).collect()).unwrap();
+    let other = EvaluationDomain::from_coeffs(b.iter().cloned().map(Scalar).collect()).unwrap();
+
+    domain.add_assign(&worker, &other);
+    for (sum, (a, b)) in domain.as_fr().iter().zip(a.iter().zip(b.iter())) {
+        let mut expected = *a;
+        expected.add_assign(b);
+        assert!(*sum == expected);
+    }
+
+    domain.sub_assign(&worker, &other);
+    assert!(domain.as_fr()[..100] == a[..]);
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn eval_at_root() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    for log_d in 0..10 {
+        let d = 1 << log_d;
+        let coeffs = (0..d)
+            .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+            .collect::<Vec<_>>();
+        let domain = EvaluationDomain::from_coeffs(coeffs.clone()).unwrap();
+        let mut evals = EvaluationDomain::from_coeffs(coeffs).unwrap();
+        evals.fft(&worker, &mut None).unwrap();
+
+        for k in 0..d {
+            assert_eq!(domain.eval_at_root(k, &worker).unwrap(), evals.as_fr()[k]);
+        }
+        // Indices wrap around the domain
+        assert_eq!(
+            domain.eval_at_root(d + 1, &worker).unwrap(),
+            domain.eval_at_root(1, &worker).unwrap()
+        );
+        assert!(matches!(
+            evals.eval_at_root(1, &worker),
+            Err(SynthesisError::InvalidDomainForm)
+        ));
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn batch_fft_consistency() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    for log_n in 0..8 {
+        let omega = RootsOfUnity::<Bls12>::get().omega_for_exp(log_n);
+        let mut polys = (0..5)
+            .map(|_| (0..1 << log_n).map(|_| Fr::random(rng)).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let expected = polys
+            .iter()
+            .map(|poly| {
+                let mut poly = poly
+                    .iter()
+                    .cloned()
+                    .map(Scalar::<Bls12>)
+                    .collect::<Vec<_>>();
+                serial_fft(&mut poly, &omega, log_n);
+                poly
+            })
+            .collect::<Vec<_>>();
+
+        batch_fft_shared_twiddles::<Bls12>(&mut polys, &omega, log_n, &worker);
+        for (poly, expected) in polys.iter().zip(expected.iter()) {
+            assert!(poly[..] == *scalars_as_fr(expected));
+        }
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn checked_z_inverse() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+
+    let domain = EvaluationDomain::<Bls12, _>::from_coeffs(vec![Scalar(Fr::one()); 16]).unwrap();
+    for i in 0..16 {
+        let root = domain.omega.pow(&[i]);
+        assert!(matches!(
+            domain.checked_z_inverse(&root),
+            Err(SynthesisError::DivisionByZero)
+        ));
+    }
+
+    let tau = Fr::random(rng);
+    let mut product = domain.checked_z_inverse(&tau).unwrap();
+    product.mul_assign(&domain.z(&tau));
+    assert!(product == Fr::one());
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn max_log_tasks() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    assert_eq!(planned_log_cpus(&worker, 10, Some(0)), 0);
+    assert_eq!(
+        planned_log_cpus(&worker, 10, Some(1)),
+        worker.log_num_cpus().min(1)
+    );
+
+    let coeffs = (0..1 << 10)
+        .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+        .collect::<Vec<_>>();
+    let mut expected = EvaluationDomain::from_coeffs(coeffs.clone()).unwrap();
+    expected.fft(&worker, &mut None).unwrap();
+
+    for log_tasks in 0..4 {
+        let mut domain = EvaluationDomain::from_coeffs(coeffs.clone()).unwrap();
+        domain.set_max_log_tasks(log_tasks);
+        assert!(domain.planned_parallelism(&worker) <= log_tasks);
+
+        domain.fft(&worker, &mut None).unwrap();
+        assert!(domain.coeffs == expected.coeffs);
+        domain.ifft(&worker, &mut None).unwrap();
+        assert!(domain.coeffs == coeffs);
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn trim_trailing_zeros() {
+    use crate::bls::{Bls12, Fr};
+
+    let worker = Worker::new();
+
+    let mut coeffs = vec![Scalar::<Bls12>(Fr::zero()); 64];
+    coeffs[4] = Scalar(Fr::one());
+    let mut domain = EvaluationDomain::from_coeffs(coeffs).unwrap();
+    domain.trim_trailing_zeros().unwrap();
+    assert_eq!(domain.exp, 3);
+    assert!(domain.as_fr()[4] == Fr::one());
+    assert_eq!(domain.degree(), Some(4));
+
+    // The smaller domain is fully functional
+    let mut expected = EvaluationDomain::from_coeffs(domain.coeffs.clone()).unwrap();
+    expected.fft(&worker, &mut None).unwrap();
+    domain.fft(&worker, &mut None).unwrap();
+    assert!(domain.coeffs == expected.coeffs);
+
+    let mut zero = EvaluationDomain::<Bls12, _>::from_coeffs(vec![Scalar(Fr::zero()); 16]).unwrap();
+    zero.trim_trailing_zeros().unwrap();
+    assert_eq!(zero.exp, 0);
+    assert!(zero.as_fr() == [Fr::zero()]);
+}
+
+#[cfg(feature = "self-profile")]
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn timing_report() {
+    use crate::bls::{Bls12, Fr};
+
+    let worker = Worker::new();
+    let calls = |method| {
+        domain_timing_report()
+            .into_iter()
+            .find(|(name, _, _)| *name == method)
+            .unwrap()
+            .1
+    };
+
+    let (fft_calls, coset_calls) = (calls("fft"), calls("coset_fft"));
+    let mut domain =
+        EvaluationDomain::<Bls12, _>::from_coeffs(vec![Scalar(Fr::one()); 16]).unwrap();
+    domain.coset_fft(&worker, &mut None).unwrap();
+    assert!(calls("fft") > fft_calls);
+    assert!(calls("coset_fft") > coset_calls);
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn from_coeffs_cow() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    let coeffs = (0..5)
+        .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+        .collect::<Vec<_>>();
+    let mut expected = EvaluationDomain::from_coeffs(coeffs.clone()).unwrap();
+
+    let mut domain = EvaluationDomain::from_coeffs_cow(&coeffs).unwrap();
+    let (point, tau) = (Fr::random(rng), Fr::random(rng));
+    assert!(domain.evaluate(&point) == expected.evaluate(&point));
+    assert!(domain.z(&tau) == expected.z(&tau));
+    assert_eq!(domain.as_ref().as_ptr(), coeffs.as_ptr());
+    assert!(!domain.is_owned());
+
+    domain.to_mut().fft(&worker, &mut None).unwrap();
+    expected.fft(&worker, &mut None).unwrap();
+    assert!(domain.is_owned());
+    assert!(domain.z(&tau) == expected.z(&tau));
+    assert!(domain.into_owned().coeffs == expected.coeffs);
+}
+
+#[cfg(feature = "test-utils")]
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn verify_quotient() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    let domain = EvaluationDomain::<Bls12, _>::random(8, rng);
+    let quotient = (0..5).map(|_| Fr::random(rng)).collect::<Vec<_>>();
+
+    // numerator = quotient * (x^8 - 1), with padding on either side
+    let mut numerator = vec![Fr::zero(); 16];
+    for (i, q) in quotient.iter().enumerate() {
+        numerator[i].sub_assign(q);
+        numerator[i + 8].add_assign(q);
+    }
+    let mut padded = quotient.clone();
+    padded.resize(8, Fr::zero());
+    assert!(domain.verify_quotient(&numerator, &quotient, &worker));
+    assert!(domain.verify_quotient(&numerator[..13], &padded, &worker));
+
+    numerator[3].add_assign(&Fr::one());
+    assert!(!domain.verify_quotient(&numerator, &quotient, &worker));
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn z_derivative_at_root() {
+    use crate::bls::{Bls12, Fr};
+
+    let domain =
+        EvaluationDomain::<Bls12, _>::from_coeffs(vec![Scalar::<Bls12>(Fr::zero()); 8]).unwrap();
+    let m = Fr::from_str("8").unwrap();
+
+    // z'(x) = m * x^{m - 1}
+    for k in 0..10 {
+        let mut expected = domain.omega.pow(&[(k * 7) as u64]);
+        expected.mul_assign(&m);
+        assert_eq!(domain.z_derivative_at_root(k), expected);
+    }
+
+    // Coefficients appended by `extend` count even before the domain is padded
+    let mut coeffs = vec![Scalar::<Bls12>(Fr::zero()); 8];
+    let mut extended = EvaluationDomain::<Bls12, _>::from_coeffs(coeffs.clone()).unwrap();
+    extended.extend(vec![Fr::one(); 3]);
+    coeffs.extend(vec![Scalar(Fr::one()); 3]);
+    let padded = EvaluationDomain::<Bls12, _>::from_coeffs(coeffs).unwrap();
+    for k in 0..20 {
+        assert_eq!(
+            extended.z_derivative_at_root(k),
+            padded.z_derivative_at_root(k)
+        );
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn new_coset() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    let coeffs = (0..13)
+        .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+        .collect::<Vec<_>>();
+    let mut expected = EvaluationDomain::from_coeffs(coeffs.clone()).unwrap();
+    expected.coset_fft(&worker, &mut None).unwrap();
+
+    let domain = EvaluationDomain::new_coset(coeffs, &worker, &mut None).unwrap();
+    assert_eq!(domain.form(), Form::Evaluations);
+    assert!(domain.coeffs == expected.coeffs);
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn iter_roots_with_values() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    let coeffs = (0..16).map(|_| Fr::random(rng)).collect::<Vec<_>>();
+    let mut domain =
+        EvaluationDomain::<Bls12, _>::from_coeffs(coeffs.iter().map(|&c| Scalar(c)).collect())
+            .unwrap();
+    domain.fft(&worker, &mut None).unwrap();
+
+    let points = domain.iter_roots_with_values(&worker).unwrap();
+    assert_eq!(points.len(), 16);
+    assert_eq!(points[0].0, Fr::one());
+    assert_eq!(points[1].0, domain.omega);
+    for (x, y) in points {
+        assert_eq!(horner::<Bls12>(&coeffs, &x), y.0);
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn divide_by_x() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+
+    let coeffs = (0..8)
+        .map(|i| Scalar::<Bls12>(if i == 0 { Fr::zero() } else { Fr::random(rng) }))
+        .collect::<Vec<_>>();
+    let mut domain = EvaluationDomain::from_coeffs(coeffs.clone()).unwrap();
+    domain.divide_by_x().unwrap();
+    assert_eq!(domain.exp, 3);
+    assert!(domain.coeffs[..7] == coeffs[1..]);
+    assert!(domain.coeffs[7] == Scalar(Fr::zero()));
+
+    assert!(matches!(
+        domain.divide_by_x(),
+        Err(SynthesisError::DivisionByZero)
+    ));
+    assert!(domain.coeffs[..7] == coeffs[1..]);
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn minv_by_doubling() {
+    use crate::bls::Fr;
+
+    for exp in 0..=Fr::S {
+        let expected = Fr::from_str(&format!("{}", 1u64 << exp)).unwrap();
+        assert_eq!(domain_size::<Fr>(exp), expected);
+        assert_eq!(domain_size::<Fr>(exp).inverse(), expected.inverse());
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn reduce_mod_cyclic() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+
+    let coeffs = (0..16).map(|_| Fr::random(rng)).collect::<Vec<_>>();
+    let domain =
+        EvaluationDomain::<Bls12, _>::from_coeffs(coeffs.iter().map(|&c| Scalar(c)).collect())
+            .unwrap();
+
+    // x^k = 1 on the k-th roots of unity, so the reduction agrees there
+    for &k in &[1, 2, 4, 8, 16] {
+        let folded = domain.reduce_mod_cyclic(k).unwrap();
+        assert_eq!(folded.len(), k);
+        let omega = RootsOfUnity::<Bls12>::get().omega_for_exp(k.trailing_zeros());
+        for i in 0..k as u64 {
+            let point = omega.pow(&[i]);
+            assert_eq!(
+                horner::<Bls12>(&folded, &point),
+                horner::<Bls12>(&coeffs, &point)
+            );
+        }
+    }
+
+    for &k in &[0, 3, 32] {
+        assert!(matches!(
+            domain.reduce_mod_cyclic(k),
+            Err(SynthesisError::InvalidDomainSize)
+        ));
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn map_in_place() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    let coeffs = (0..100)
+        .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+        .collect::<Vec<_>>();
+    let mut domain = EvaluationDomain::from_coeffs(coeffs.clone()).unwrap();
+    let by = Fr::random(rng);
+    domain.map_in_place(&worker, |c| c.group_mul_assign(&by));
+
+    assert_eq!(domain.coeffs.len(), 128);
+    for (c, expected) in domain.coeffs.iter().zip(&coeffs) {
+        let mut expected = expected.0;
+        expected.mul_assign(&by);
+        assert_eq!(c.0, expected);
+    }
+    assert!(domain.coeffs[100..].iter().all(|c| c.0.is_zero()));
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn from_coeffs_with_max_exp() {
+    use crate::bls::{Bls12, Fr};
+
+    let coeffs = vec![Scalar::<Bls12>(Fr::one()); 9];
+
+    let domain = EvaluationDomain::from_coeffs_with_max_exp(coeffs.clone(), 4).unwrap();
+    assert_eq!(domain.exp, 4);
+    let domain = EvaluationDomain::from_coeffs_with_max_exp(coeffs[..8].to_vec(), 3).unwrap();
+    assert_eq!(domain.exp, 3);
+    assert!(matches!(
+        EvaluationDomain::from_coeffs_with_max_exp(coeffs, 3),
+        Err(SynthesisError::PolynomialDegreeTooLarge)
+    ));
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn fft_plan() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    for exp in 0..10 {
+        let plan = FftPlan::<Bls12>::new(exp, &worker).unwrap();
+        assert_eq!(plan.exp(), exp);
+
+        let coeffs = (0..1 << exp).map(|_| Fr::random(rng)).collect::<Vec<_>>();
+        let mut expected =
+            EvaluationDomain::<Bls12, _>::from_coeffs(coeffs.iter().map(|&c| Scalar(c)).collect())
+                .unwrap();
+        expected.fft(&worker, &mut None).unwrap();
+
+        let mut evals = coeffs.clone();
+        plan.forward(&mut evals, &worker);
+        assert!(evals.iter().zip(&expected.coeffs).all(|(e, x)| *e == x.0));
+        plan.inverse(&mut evals, &worker);
+        assert_eq!(evals, coeffs);
+
+        let mut domain =
+            EvaluationDomain::<Bls12, _>::from_coeffs(coeffs.iter().map(|&c| Scalar(c)).collect())
+                .unwrap();
+        domain.fft_with_plan(&worker, &plan);
+        assert!(domain.coeffs == expected.coeffs);
+        domain.ifft_with_plan(&worker, &plan);
+        assert_eq!(domain.form(), Form::Coefficients);
+        assert!(domain.coeffs.iter().zip(&coeffs).all(|(c, x)| c.0 == *x));
+    }
+
+    assert!(FftPlan::<Bls12>::new(Fr::S, &worker).is_err());
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn barycentric_eval() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    let coeffs = (0..16).map(|_| Fr::random(rng)).collect::<Vec<_>>();
+    let mut domain =
+        EvaluationDomain::<Bls12, _>::from_coeffs(coeffs.iter().map(|&c| Scalar(c)).collect())
+            .unwrap();
+    domain.fft(&worker, &mut None).unwrap();
+
+    for _ in 0..10 {
+        let z = Fr::random(rng);
+        assert_eq!(
+            domain.barycentric_eval(&z, &worker).unwrap(),
+            horner::<Bls12>(&coeffs, &z)
+        );
+    }
+
+    assert!(matches!(
+        domain.barycentric_eval(&domain.omega, &worker),
+        Err(SynthesisError::DivisionByZero)
+    ));
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn split_at() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+
+    let coeffs = (0..16)
+        .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+        .collect::<Vec<_>>();
+    let domain = || EvaluationDomain::from_coeffs(coeffs.clone()).unwrap();
+
+    let (low, high) = domain().split_at(4).unwrap();
+    assert_eq!((low.exp, high.exp), (2, 4));
+    assert!(low.coeffs[..] == coeffs[..4]);
+    assert!(high.coeffs[..12] == coeffs[4..]);
+    assert!(high.coeffs[12..].iter().all(|c| c.0.is_zero()));
+
+    let (low, high) = domain().split_at(16).unwrap();
+    assert!(low.coeffs == coeffs);
+    assert_eq!(high.coeffs.len(), 1);
+
+    for &mid in &[0, 3, 32] {
+        assert!(matches!(
+            domain().split_at(mid),
+            Err(SynthesisError::InvalidDomainSize)
+        ));
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn with_capacity() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    let coeffs = (0..100).map(|_| Fr::random(rng)).collect::<Vec<_>>();
+    let mut domain = EvaluationDomain::<Bls12, Scalar<Bls12>>::with_capacity(100);
+    assert_eq!(domain.as_ref().len(), 1);
+    assert_eq!(domain.degree(), None);
+    let capacity = domain.coeffs.capacity();
+    assert!(capacity >= 128);
+
+    domain.extend(coeffs.iter().cloned());
+    assert_eq!(domain.coeffs.capacity(), capacity);
+
+    let mut expected =
+        EvaluationDomain::<Bls12, _>::from_coeffs(coeffs.iter().map(|&c| Scalar(c)).collect())
+            .unwrap();
+    domain.fft(&worker, &mut None).unwrap();
+    expected.fft(&worker, &mut None).unwrap();
+    assert_eq!(domain.exp, 7);
+    assert_eq!(domain.coeffs.capacity(), capacity);
+    assert!(domain.coeffs == expected.coeffs);
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn with_capacity_before_extend() {
+    use crate::bls::{Bls12, Fr};
+
+    // The empty domain still has the one coefficient its size promises
+    let mut domain = EvaluationDomain::<Bls12, Scalar<Bls12>>::with_capacity(100);
+    assert_eq!(domain.z_derivative_at_root(3), Fr::one());
+    domain.reduce_mod_vanishing(0).unwrap();
+    domain.divide_by_x().unwrap();
+    assert_eq!(domain.degree(), None);
+    assert_eq!(domain.as_ref().len(), 1);
+
+    // Once extended, methods other than the transforms see the final size
+    let worker = Worker::new();
+    let coeffs = vec![Scalar::<Bls12>(Fr::one()); 5];
+    let expected = EvaluationDomain::<Bls12, _>::from_coeffs(coeffs.clone()).unwrap();
+    let mut domain = EvaluationDomain::<Bls12, Scalar<Bls12>>::with_capacity(100);
+    domain.extend(coeffs.iter().map(|c| c.0));
+    let tau = Fr::from_str("3").unwrap();
+    assert_eq!(domain.z(&tau), expected.z(&tau));
+    for k in 0..8 {
+        assert_eq!(
+            domain.eval_at_root(k, &worker).unwrap(),
+            expected.eval_at_root(k, &worker).unwrap()
+        );
+    }
+    let capacity = domain.coeffs.capacity();
+    domain.map_in_place(&worker, |c| c.0.double());
+    assert_eq!(domain.coeffs.capacity(), capacity);
+    assert!(domain.as_ref()[..5] == [Scalar(Fr::from_str("2").unwrap()); 5]);
+    assert_eq!(domain.as_ref().len(), 8);
+}
+
+// The length check is only skipped by release builds with `unchecked`
+#[cfg(any(not(feature = "unchecked"), debug_assertions))]
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+#[should_panic]
+fn mul_assign_of_different_sizes() {
+    use crate::bls::{Bls12, Fr};
+
+    let worker = Worker::new();
+    let mut a = EvaluationDomain::<Bls12, _>::from_coeffs(vec![Scalar(Fr::one()); 4]).unwrap();
+    let b = EvaluationDomain::<Bls12, _>::from_coeffs(vec![Scalar(Fr::one()); 8]).unwrap();
+    a.mul_assign(&worker, &b);
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn coeffs_hash() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+
+    let coeffs = (0..5).map(|_| Fr::random(rng)).collect::<Vec<_>>();
+    let domain =
+        EvaluationDomain::<Bls12, _>::from_coeffs(coeffs.iter().cloned().map(Scalar).collect())
+            .unwrap();
+
+    // The padding is hashed whether or not it was applied yet
+    let mut extended = EvaluationDomain::<Bls12, _>::from_coeffs(vec![Scalar(coeffs[0])]).unwrap();
+    extended.extend(coeffs[1..].iter().cloned());
+    assert_eq!(domain.coeffs_hash(), extended.coeffs_hash());
+
+    let mut other = coeffs;
+    other[4].add_assign(&Fr::one());
+    let other =
+        EvaluationDomain::<Bls12, _>::from_coeffs(other.into_iter().map(Scalar).collect()).unwrap();
+    assert_ne!(domain.coeffs_hash(), other.coeffs_hash());
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn mul_by_constant() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    let poly = (0..5).map(|_| Fr::random(rng)).collect::<Vec<_>>();
+    let c = Fr::random(rng);
+    let expected = poly
+        .iter()
+        .map(|p| {
+            let mut p = *p;
+            p.mul_assign(&c);
+            Scalar::<Bls12>(p)
+        })
+        .collect::<Vec<_>>();
+
+    let domain = |coeffs: &[Fr]| {
+        EvaluationDomain::<Bls12, _>::from_coeffs(coeffs.iter().cloned().map(Scalar).collect())
+            .unwrap()
+    };
+    // A constant in a larger domain has degree zero, too
+    let constant = domain(&[c, Fr::zero(), Fr::zero()]);
+
+    for (a, b) in &[(&domain(&poly), &constant), (&constant, &domain(&poly))] {
+        let product = EvaluationDomain::mul(a, b, &worker, &mut None).unwrap();
+        assert_eq!(product.exp, 3);
+        assert!(product.coeffs[..5] == expected[..]);
+        assert!(product.coeffs[5..].iter().all(|c| c.0.is_zero()));
+    }
+
+    let mut product = domain(&poly);
+    product
+        .checked_mul_assign(&worker, &mut None, &[c, Fr::zero()])
+        .unwrap();
+    assert!(product.coeffs[..5] == expected[..]);
+
+    let mut product = domain(&[c]);
+    product
+        .checked_mul_assign(&worker, &mut None, &poly)
+        .unwrap();
+    assert_eq!(product.exp, 3);
+    assert!(product.coeffs[..5] == expected[..]);
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn polynomial_div_rem() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
+
+    for num_len in 0..40 {
+        for den_len in 1..20 {
+            let num = (0..num_len).map(|_| Fr::random(rng)).collect::<Vec<_>>();
+            let mut den = (0..den_len).map(|_| Fr::random(rng)).collect::<Vec<_>>();
+            // Trailing zeros don't change the divisor
+            den.push(Fr::zero());
 
-        for (idx, a) in a.chunks_mut(chunk).enumerate() {
-            scope.spawn(move |_scope| {
-                let mut idx = idx * chunk;
-                let mask = (1 << log_cpus) - 1;
-                for a in a {
-                    *a = tmp[idx & mask][idx >> log_cpus];
-                    idx += 1;
-                }
-            });
+            let (quotient, rem) = div_rem::<Bls12>(&num, &den, &worker).unwrap();
+            assert!(rem.len() < den_len);
+
+            // num = quotient·den + rem
+            let mut expected = naive_mul::<Bls12>(&quotient, &den, &worker);
+            expected.resize(num_len.max(expected.len()), Fr::zero());
+            for (e, r) in expected.iter_mut().zip(&rem) {
+                e.add_assign(r);
+            }
+            assert!(expected[..num_len] == num[..]);
+            assert!(expected[num_len..].iter().all(|c| c.is_zero()));
         }
-    });
+    }
+
+    assert!(matches!(
+        div_rem::<Bls12>(&[Fr::one()], &[Fr::zero()], &worker),
+        Err(SynthesisError::DivisionByZero)
+    ));
 }
 
-// Test multiplying various (low degree) polynomials together and
-// comparing with naive evaluations.
-#[cfg(any(feature = "pairing", features = "blst"))]
+#[cfg(any(feature = "pairing", feature = "blst"))]
 #[test]
-fn polynomial_arith() {
-    use crate::bls::{Bls12, Engine};
-    use rand_core::RngCore;
+fn checked_coset_fft() {
+    use crate::bls::{Bls12, Fr};
 
-    fn test_mul<E: ScalarEngine + Engine, R: RngCore>(rng: &mut R) {
-        let worker = Worker::new();
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
 
-        for coeffs_a in 0..70 {
-            for coeffs_b in 0..70 {
-                let mut a: Vec<_> = (0..coeffs_a)
-                    .map(|_| Scalar::<E>(E::Fr::random(rng)))
-                    .collect();
-                let mut b: Vec<_> = (0..coeffs_b)
-                    .map(|_| Scalar::<E>(E::Fr::random(rng)))
-                    .collect();
+    let coeffs = (0..16)
+        .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+        .collect::<Vec<_>>();
+    let mut expected = EvaluationDomain::from_coeffs(coeffs.clone()).unwrap();
+    expected.coset_fft(&worker, &mut None).unwrap();
 
-                // naive evaluation
-                let mut naive = vec![Scalar(E::Fr::zero()); coeffs_a + coeffs_b];
-                for (i1, a) in a.iter().enumerate() {
-                    for (i2, b) in b.iter().enumerate() {
-                        let mut prod = *a;
-                        prod.group_mul_assign(&b.0);
-                        naive[i1 + i2].group_add_assign(&prod);
-                    }
-                }
+    let mut domain = EvaluationDomain::from_coeffs(coeffs).unwrap();
+    assert_eq!(
+        domain.checked_coset_fft(&worker, &mut None).unwrap(),
+        FftBackend::Cpu
+    );
+    assert_eq!(domain.form(), Form::Evaluations);
+    assert!(domain.coeffs == expected.coeffs);
+}
 
-                a.resize(coeffs_a + coeffs_b, Scalar(E::Fr::zero()));
-                b.resize(coeffs_a + coeffs_b, Scalar(E::Fr::zero()));
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn prefix_product_matches_serial() {
+    use crate::bls::{Bls12, Fr};
 
-                let mut a = EvaluationDomain::from_coeffs(a).unwrap();
-                let mut b = EvaluationDomain::from_coeffs(b).unwrap();
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
 
-                a.fft(&worker, &mut None).unwrap();
-                b.fft(&worker, &mut None).unwrap();
-                a.mul_assign(&worker, &b);
-                a.ifft(&worker, &mut None).unwrap();
+    for len in 1..100 {
+        let coeffs = (0..len).map(|_| Fr::random(rng)).collect::<Vec<_>>();
+        let domain =
+            EvaluationDomain::<Bls12, _>::from_coeffs(coeffs.iter().cloned().map(Scalar).collect())
+                .unwrap();
 
-                for (naive, fft) in naive.iter().zip(a.coeffs.iter()) {
-                    assert!(naive == fft);
-                }
-            }
+        let mut expected = Vec::new();
+        let mut acc = Fr::one();
+        for c in domain.as_fr() {
+            acc.mul_assign(c);
+            expected.push(acc);
         }
+        assert_eq!(domain.prefix_product(&worker), expected);
     }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn grand_product_of_ratios() {
+    use crate::bls::{Bls12, Fr};
 
     let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
 
-    test_mul::<Bls12, _>(rng);
+    let numerators = (0..37).map(|_| Fr::random(rng)).collect::<Vec<_>>();
+    let mut denominators = (0..37).map(|_| Fr::random(rng)).collect::<Vec<_>>();
+    let z = grand_product::<Bls12>(&numerators, &denominators, &worker).unwrap();
+
+    let mut acc = Fr::one();
+    for ((n, d), z) in numerators.iter().zip(&denominators).zip(&z) {
+        acc.mul_assign(n);
+        acc.mul_assign(&d.inverse().unwrap());
+        assert_eq!(acc, *z);
+    }
+
+    // A permutation of the numerators closes the product
+    let z = grand_product::<Bls12>(
+        &numerators,
+        &numerators[..].iter().rev().cloned().collect::<Vec<_>>(),
+        &worker,
+    )
+    .unwrap();
+    assert_eq!(z[36], Fr::one());
+
+    assert!(matches!(
+        grand_product::<Bls12>(&numerators, &denominators[1..], &worker),
+        Err(SynthesisError::InvalidDomainSize)
+    ));
+    denominators[5] = Fr::zero();
+    assert!(matches!(
+        grand_product::<Bls12>(&numerators, &denominators, &worker),
+        Err(SynthesisError::DivisionByZero)
+    ));
 }
 
 #[cfg(any(feature = "pairing", feature = "blst"))]
 #[test]
-fn fft_composition() {
+fn affine_point_fft() {
     use crate::bls::{Bls12, Engine};
-    use rand_core::RngCore;
 
-    fn test_comp<E: ScalarEngine + Engine, R: RngCore>(rng: &mut R) {
-        let worker = Worker::new();
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
 
-        for coeffs in 0..10 {
-            let coeffs = 1 << coeffs;
+    let points = (0..8)
+        .map(|_| <Bls12 as Engine>::G1::random(rng))
+        .collect::<Vec<_>>();
+    let mut projective =
+        EvaluationDomain::<Bls12, _>::from_coeffs(points.iter().cloned().map(Point).collect())
+            .unwrap();
+    let mut affine = EvaluationDomain::<Bls12, _>::from_coeffs(
+        points
+            .iter()
+            .map(|p| AffinePoint(p.into_affine()))
+            .collect(),
+    )
+    .unwrap();
 
-            let mut v = vec![];
-            for _ in 0..coeffs {
-                v.push(Scalar::<E>(E::Fr::random(rng)));
-            }
+    projective.fft(&worker, &mut None).unwrap();
+    affine.fft(&worker, &mut None).unwrap();
+    for (p, a) in projective.as_ref().iter().zip(affine.as_ref()) {
+        assert_eq!(p.0.into_affine(), a.0);
+    }
 
-            let mut domain = EvaluationDomain::from_coeffs(v.clone()).unwrap();
-            domain.ifft(&worker, &mut None).unwrap();
-            domain.fft(&worker, &mut None).unwrap();
-            assert!(v == domain.coeffs);
-            domain.fft(&worker, &mut None).unwrap();
-            domain.ifft(&worker, &mut None).unwrap();
-            assert!(v == domain.coeffs);
-            domain.icoset_fft(&worker, &mut None).unwrap();
-            domain.coset_fft(&worker, &mut None).unwrap();
-            assert!(v == domain.coeffs);
-            domain.coset_fft(&worker, &mut None).unwrap();
-            domain.icoset_fft(&worker, &mut None).unwrap();
-            assert!(v == domain.coeffs);
-        }
+    affine.ifft(&worker, &mut None).unwrap();
+    for (p, a) in points.iter().zip(affine.as_ref()) {
+        assert_eq!(p.into_affine(), a.0);
     }
+}
+
+#[cfg(not(feature = "gpu"))]
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn try_fft_without_gpu() {
+    use crate::bls::{Bls12, Fr};
 
     let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
 
-    test_comp::<Bls12, _>(rng);
+    let coeffs = (0..16)
+        .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+        .collect::<Vec<_>>();
+    let mut expected = EvaluationDomain::from_coeffs(coeffs.clone()).unwrap();
+    let mut domain = EvaluationDomain::from_coeffs(coeffs.clone()).unwrap();
+
+    expected.fft(&worker, &mut None).unwrap();
+    domain.try_fft(&worker).unwrap();
+    assert!(domain.coeffs == expected.coeffs);
+    domain.try_ifft(&worker).unwrap();
+    assert!(domain.coeffs == coeffs);
+
+    expected.ifft(&worker, &mut None).unwrap();
+    expected.coset_fft(&worker, &mut None).unwrap();
+    domain.try_coset_fft(&worker).unwrap();
+    assert!(domain.coeffs == expected.coeffs);
+    domain.try_icoset_fft(&worker).unwrap();
+    assert!(domain.coeffs == coeffs);
 }
 
 #[cfg(any(feature = "pairing", feature = "blst"))]
 #[test]
-fn parallel_fft_consistency() {
-    use crate::bls::{Bls12, Engine};
-    use rand_core::RngCore;
-    use std::cmp::min;
+fn pad_to_match() {
+    use crate::bls::{Bls12, Fr};
 
-    fn test_consistency<E: ScalarEngine + Engine, R: RngCore>(rng: &mut R) {
-        let worker = Worker::new();
+    let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
 
-        for _ in 0..5 {
-            for log_d in 0..10 {
-                let d = 1 << log_d;
+    let mut domain = |len: usize| {
+        let coeffs = (0..len)
+            .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+            .collect::<Vec<_>>();
+        EvaluationDomain::from_coeffs(coeffs).unwrap()
+    };
+    let mut small = domain(3);
+    let large = domain(12);
+    let expected = small.coeffs.clone();
 
-                let v1 = (0..d)
-                    .map(|_| Scalar::<E>(E::Fr::random(rng)))
-                    .collect::<Vec<_>>();
-                let mut v1 = EvaluationDomain::from_coeffs(v1).unwrap();
-                let mut v2 = EvaluationDomain::from_coeffs(v1.coeffs.clone()).unwrap();
+    small.pad_to_match(&large).unwrap();
+    assert_eq!(small.exp, large.exp);
+    assert!(small.omega == large.omega && small.minv == large.minv);
+    assert!(small.coeffs[..4] == expected[..]);
+    assert!(small.coeffs[4..].iter().all(|c| c.0.is_zero()));
 
-                for log_cpus in log_d..min(log_d + 1, 3) {
-                    parallel_fft(&mut v1.coeffs, &worker, &v1.omega, log_d, log_cpus);
-                    serial_fft(&mut v2.coeffs, &v2.omega, log_d);
+    // Matching a smaller or transformed domain fails
+    let mut large = large;
+    assert!(matches!(
+        large.pad_to_match(&domain(3)),
+        Err(SynthesisError::InvalidDomainSize)
+    ));
+    large.fft(&worker, &mut None).unwrap();
+    assert!(matches!(
+        small.pad_to_match(&large),
+        Err(SynthesisError::InvalidDomainForm)
+    ));
+}
 
-                    assert!(v1.coeffs == v2.coeffs);
-                }
-            }
-        }
+#[cfg(feature = "fft-trace")]
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn serial_fft_traced_stages() {
+    use crate::bls::{Bls12, Fr};
+
+    let rng = &mut rand::thread_rng();
+
+    let log_n = 5;
+    let omega = RootsOfUnity::<Bls12>::get().omega_for_exp(log_n);
+    let coeffs = (0..1 << log_n)
+        .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+        .collect::<Vec<_>>();
+
+    let mut expected = coeffs.clone();
+    serial_fft(&mut expected, &omega, log_n);
+
+    let mut stages = Vec::new();
+    let mut a = coeffs;
+    serial_fft_traced(&mut a, &omega, log_n, |stage, a| {
+        stages.push((stage, a.to_vec()))
+    });
+    assert!(a == expected);
+    assert_eq!(stages.len(), log_n as usize);
+    for (i, (stage, snapshot)) in stages.iter().enumerate() {
+        assert_eq!(*stage, i);
+        assert_eq!(snapshot.len(), 1 << log_n);
     }
+    assert!(stages[log_n as usize - 1].1 == expected);
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn from_roots() {
+    use crate::bls::{Bls12, Fr};
 
     let rng = &mut rand::thread_rng();
+    let worker = Worker::new();
 
-    test_consistency::<Bls12, _>(rng);
+    for k in 0..20 {
+        let roots = (0..k).map(|_| Fr::random(rng)).collect::<Vec<_>>();
+        let poly = EvaluationDomain::<Bls12, _>::from_roots(&roots, &worker, &mut None).unwrap();
+        assert_eq!(poly.degree(), Some(k));
+        assert_eq!(poly.as_fr()[k], Fr::one());
+
+        for r in &roots {
+            assert_eq!(horner::<Bls12>(poly.as_fr(), r), Fr::zero());
+        }
+
+        let x = Fr::random(rng);
+        let mut expected = Fr::one();
+        for r in &roots {
+            let mut factor = x;
+            factor.sub_assign(r);
+            expected.mul_assign(&factor);
+        }
+        assert_eq!(horner::<Bls12>(poly.as_fr(), &x), expected);
+    }
+}
+
+#[cfg(not(feature = "gpu"))]
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn calibrate_fft_threshold_without_gpu() {
+    use crate::bls::Bls12;
+
+    let worker = Worker::new();
+    let mut kern = gpu::LockedFFTKernel::<Bls12>::new(CALIBRATION_MAX_LOG_N as usize, false);
+
+    assert_eq!(
+        calibrate_fft_threshold(&mut kern, &worker),
+        <Bls12 as ScalarEngine>::Fr::S
+    );
+}
+
+/// Keeps a single FFT kernel around for transforms of varying sizes. A kernel
+/// is reused for every domain it is large enough for, and only replaced by a
+/// larger one when needed, so device memory isn't reallocated for every proof.
+pub struct KernelCache<E: Engine> {
+    kernel: Option<gpu::LockedFFTKernel<E>>,
+    log_d: usize,
+    priority: bool,
+}
+
+impl<E: Engine> KernelCache<E> {
+    pub fn new(priority: bool) -> Self {
+        KernelCache {
+            kernel: None,
+            log_d: 0,
+            priority,
+        }
+    }
+
+    /// Returns a kernel for domains of up to `2^log_d` elements, to be passed
+    /// to e.g. [`EvaluationDomain::fft`].
+    pub fn get_or_create(&mut self, log_d: usize) -> &mut Option<gpu::LockedFFTKernel<E>> {
+        if self.kernel.is_none() || log_d > self.log_d {
+            // Free the device memory of the old kernel first
+            self.kernel = None;
+            self.kernel = Some(gpu::LockedFFTKernel::new(log_d, self.priority));
+            self.log_d = log_d;
+        }
+        &mut self.kernel
+    }
+
+    /// Returns the `log_d` of the cached kernel, if any.
+    pub fn log_d(&self) -> Option<usize> {
+        self.kernel.as_ref().map(|_| self.log_d)
+    }
 }
 
 pub fn create_fft_kernel<E>(log_d: usize, priority: bool) -> Option<gpu::FFTKernel<E>>
+where
+    E: Engine,
+{
+    try_create_fft_kernel(log_d, priority).ok()
+}
+
+/// Like [`create_fft_kernel`], but returns the error so callers can tell
+/// transient failures (see [`gpu::GPUError::is_retryable`]) from fatal ones.
+pub fn try_create_fft_kernel<E>(log_d: usize, priority: bool) -> gpu::GPUResult<gpu::FFTKernel<E>>
 where
     E: Engine,
 {
     match gpu::FFTKernel::create(1 << log_d, priority) {
-        Ok(k) => {
+        Ok(mut k) => {
             info!("GPU FFT kernel instantiated!");
-            Some(k)
+            // Pay the latency of the first launches now, not in the first proof
+            if let Err(e) = k.warmup() {
+                warn!("Cannot warm up GPU FFT kernel! Error: {}", e);
+            }
+            Ok(k)
         }
         Err(e) => {
-            warn!("Cannot instantiate GPU FFT kernel! Error: {}", e);
-            None
+            if e.is_retryable() {
+                warn!(
+                    "Cannot instantiate GPU FFT kernel (retryable)! Error: {}",
+                    e
+                );
+            } else {
+                warn!("Cannot instantiate GPU FFT kernel! Error: {}", e);
+            }
+            Err(e)
         }
     }
 }
@@ -579,7 +6642,9 @@ where
 #[cfg(test)]
 mod tests {
     use crate::bls::{Bls12, Fr};
-    use crate::domain::{gpu_fft, parallel_fft, serial_fft, EvaluationDomain, Scalar};
+    use crate::domain::{
+        gpu_fft, parallel_fft, serial_fft, EvaluationDomain, GpuConvolution, Scalar,
+    };
     use crate::gpu;
     use crate::multicore::Worker;
     use ff::Field;
@@ -616,7 +6681,16 @@ mod tests {
             if log_d <= log_cpus {
                 serial_fft(&mut v2.coeffs, &v2.omega, log_d);
             } else {
-                parallel_fft(&mut v2.coeffs, &worker, &v2.omega, log_d, log_cpus);
+                parallel_fft(
+                    &mut v2.coeffs,
+                    &worker,
+                    &v2.omega,
+                    log_d,
+                    log_cpus,
+                    None,
+                    None,
+                )
+                .unwrap();
             }
             let cpu_dur =
                 now.elapsed().as_secs() * 1000 as u64 + now.elapsed().subsec_millis() as u64;
@@ -628,4 +6702,179 @@ mod tests {
             println!("============================");
         }
     }
+
+    #[test]
+    pub fn gpu_fft_after_warmup() {
+        let _ = env_logger::try_init();
+
+        let rng = &mut rand::thread_rng();
+
+        let mut kern = gpu::FFTKernel::create(1 << 12, false).expect("Cannot initialize kernel!");
+        kern.warmup().unwrap();
+
+        let elems = (0..1 << 12)
+            .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+            .collect::<Vec<_>>();
+        let mut v1 = EvaluationDomain::from_coeffs(elems.clone()).unwrap();
+        let mut v2 = EvaluationDomain::from_coeffs(elems).unwrap();
+
+        gpu_fft(&mut kern, &mut v1.coeffs, &v1.omega, 12).expect("GPU FFT failed!");
+        serial_fft(&mut v2.coeffs, &v2.omega, 12);
+        assert!(v1.coeffs == v2.coeffs);
+    }
+
+    #[test]
+    pub fn device_domain_consistency() {
+        let _ = env_logger::try_init();
+
+        let rng = &mut rand::thread_rng();
+
+        let worker = Worker::new();
+        let mut kern = gpu::LockedFFTKernel::<Bls12>::new(16, false);
+
+        for log_d in 1..17 {
+            let d = 1 << log_d;
+
+            let elems = (0..d)
+                .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+                .collect::<Vec<_>>();
+            let domain = EvaluationDomain::from_coeffs(elems.clone()).unwrap();
+            let mut expected = EvaluationDomain::from_coeffs(elems.clone()).unwrap();
+            expected.fft(&worker, &mut None).unwrap();
+
+            let mut device = domain
+                .upload(kern.get_kernel().expect("Cannot initialize kernel!"))
+                .unwrap();
+            device.fft().unwrap();
+            assert!(device.download().unwrap().coeffs == expected.coeffs);
+
+            let mut device = domain.upload(kern.get_kernel().unwrap()).unwrap();
+            device.fft().unwrap();
+            device.ifft().unwrap();
+            assert!(device.download().unwrap().coeffs == elems);
+
+            let mut device = domain.upload(kern.get_kernel().unwrap()).unwrap();
+            device.add_assign(&expected).unwrap();
+            let mut sum = EvaluationDomain::from_coeffs(elems.clone()).unwrap();
+            sum.add_assign(&worker, &expected);
+            assert!(device.download().unwrap().coeffs == sum.coeffs);
+
+            let mut device = sum.upload(kern.get_kernel().unwrap()).unwrap();
+            device.sub_assign(&expected).unwrap();
+            assert!(device.download().unwrap().coeffs == elems);
+        }
+    }
+
+    #[test]
+    pub fn fft_then_ifft_consistency() {
+        let _ = env_logger::try_init();
+
+        let rng = &mut rand::thread_rng();
+
+        let worker = Worker::new();
+        let mut kern = gpu::LockedFFTKernel::<Bls12>::new(16, false);
+
+        for log_d in 1..17 {
+            let d = 1 << log_d;
+
+            let a = (0..d)
+                .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+                .collect::<Vec<_>>();
+            let b = (0..d)
+                .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+                .collect::<Vec<_>>();
+            let mut other = EvaluationDomain::from_coeffs(b.clone()).unwrap();
+            other.fft(&worker, &mut None).unwrap();
+
+            // The transforms are linear, so this adds the coefficients
+            let mut domain = EvaluationDomain::from_coeffs(a.clone()).unwrap();
+            domain
+                .fft_then_ifft(&mut kern, |device| device.add_assign(&other))
+                .unwrap();
+
+            let mut expected = EvaluationDomain::from_coeffs(a).unwrap();
+            expected.add_assign(&worker, &EvaluationDomain::from_coeffs(b).unwrap());
+            assert!(domain.coeffs == expected.coeffs);
+        }
+    }
+
+    #[test]
+    pub fn gpu_convolution_consistency() {
+        let _ = env_logger::try_init();
+
+        let rng = &mut rand::thread_rng();
+
+        let worker = Worker::new();
+        let mut kern = gpu::LockedFFTKernel::<Bls12>::new(16, false);
+
+        for log_d in 1..17 {
+            let d = 1 << log_d;
+
+            let a = (0..d).map(|_| Fr::random(rng)).collect::<Vec<_>>();
+            let b = (0..d / 2).map(|_| Fr::random(rng)).collect::<Vec<_>>();
+
+            let mut expected =
+                EvaluationDomain::from_coeffs(a.iter().map(|&c| Scalar::<Bls12>(c)).collect())
+                    .unwrap();
+            let mut other = EvaluationDomain::from_coeffs(
+                b.iter()
+                    .chain(std::iter::repeat(&Fr::zero()))
+                    .take(d)
+                    .map(|&c| Scalar::<Bls12>(c))
+                    .collect(),
+            )
+            .unwrap();
+            expected.fft(&worker, &mut None).unwrap();
+            other.fft(&worker, &mut None).unwrap();
+            expected.mul_assign(&worker, &other);
+            expected.ifft(&worker, &mut None).unwrap();
+
+            let result = GpuConvolution::new(&mut kern)
+                .convolve(&a, &b, log_d)
+                .unwrap();
+            assert!(result
+                .iter()
+                .zip(expected.coeffs.iter())
+                .all(|(r, e)| *r == e.0));
+        }
+    }
+
+    #[test]
+    pub fn gpu_commit_consistency() {
+        use crate::bls::Engine;
+        use crate::domain::Point;
+        use ff::PrimeField;
+        use groupy::CurveProjective;
+
+        let _ = env_logger::try_init();
+
+        let rng = &mut rand::thread_rng();
+
+        let worker = Worker::new();
+        let mut kern = Some(gpu::LockedMultiexpKernel::<Bls12>::new(12, false));
+
+        let srs = (0..1 << 12)
+            .map(|_| Point(<Bls12 as Engine>::G1::random(rng)))
+            .collect::<Vec<_>>();
+        let srs = EvaluationDomain::from_coeffs(srs).unwrap();
+
+        for log_d in 1..13 {
+            let d = 1 << log_d;
+
+            let coeffs = (0..d)
+                .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+                .collect::<Vec<_>>();
+            let poly = EvaluationDomain::from_coeffs(coeffs.clone()).unwrap();
+
+            let mut expected = <Bls12 as Engine>::G1::zero();
+            for (c, p) in coeffs.iter().zip(srs.as_ref()) {
+                let mut term = p.0;
+                term.mul_assign(c.0.into_repr());
+                expected.add_assign(&term);
+            }
+
+            assert_eq!(poly.commit_gpu(&srs, &worker, &mut kern).unwrap(), expected);
+            assert_eq!(poly.commit_gpu(&srs, &worker, &mut None).unwrap(), expected);
+        }
+    }
 }
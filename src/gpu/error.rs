@@ -2,10 +2,15 @@
 pub enum GPUError {
     #[error("GPUError: {0}")]
     Simple(&'static str),
+    #[error("Operation was cancelled!")]
+    Cancelled,
     #[cfg(feature = "gpu")]
     #[error("Ocl Error: {0}")]
     Ocl(ocl::Error),
     #[cfg(feature = "gpu")]
+    #[error("GPU out of memory: {0}")]
+    OutOfMemory(ocl::Error),
+    #[cfg(feature = "gpu")]
     #[error("GPU taken by a high priority process!")]
     GPUTaken,
     #[cfg(feature = "gpu")]
@@ -15,10 +20,33 @@ pub enum GPUError {
 
 pub type GPUResult<T> = std::result::Result<T, GPUError>;
 
+impl GPUError {
+    /// Returns `true` for transient failures (the device is out of memory or
+    /// taken by a high priority process) that may succeed when retried later.
+    #[cfg(feature = "gpu")]
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, GPUError::OutOfMemory(_) | GPUError::GPUTaken)
+    }
+
+    /// Returns `true` for transient failures that may succeed when retried
+    /// later. Without the `gpu` feature no error is retryable.
+    #[cfg(not(feature = "gpu"))]
+    pub fn is_retryable(&self) -> bool {
+        false
+    }
+}
+
 #[cfg(feature = "gpu")]
 impl From<ocl::Error> for GPUError {
     fn from(error: ocl::Error) -> Self {
-        GPUError::Ocl(error)
+        use ocl::core::Status;
+
+        match error.api_status() {
+            Some(Status::CL_MEM_OBJECT_ALLOCATION_FAILURE)
+            | Some(Status::CL_OUT_OF_RESOURCES)
+            | Some(Status::CL_OUT_OF_HOST_MEMORY) => GPUError::OutOfMemory(error),
+            _ => GPUError::Ocl(error),
+        }
     }
 }
 
@@ -23,6 +23,7 @@ where
     fft_dst_buffer: Buffer<structs::PrimeFieldStruct<E::Fr>>,
     fft_pq_buffer: Buffer<structs::PrimeFieldStruct<E::Fr>>,
     fft_omg_buffer: Buffer<structs::PrimeFieldStruct<E::Fr>>,
+    fft_stash_buffer: Option<Buffer<structs::PrimeFieldStruct<E::Fr>>>, // Allocated by `stash_on_device`
     _lock: locks::GPULock, // RFC 1857: struct fields are dropped in the same order as they are declared.
     priority: bool,
 }
@@ -31,6 +32,13 @@ impl<E> FFTKernel<E>
 where
     E: Engine,
 {
+    /// Returns the number of bytes of device memory a kernel created for `n`
+    /// elements allocates.
+    pub fn memory_required(n: usize) -> usize {
+        let elements = 2 * n + (1 << MAX_RADIX_DEGREE >> 1) + LOG2_MAX_ELEMENTS;
+        elements * std::mem::size_of::<E::Fr>()
+    }
+
     pub fn create(n: u32, priority: bool) -> GPUResult<FFTKernel<E>> {
         let lock = locks::GPULock::lock();
 
@@ -72,6 +80,7 @@ where
             fft_dst_buffer: dstbuff,
             fft_pq_buffer: pqbuff,
             fft_omg_buffer: omgbuff,
+            fft_stash_buffer: None,
             _lock: lock,
             priority,
         })
@@ -158,20 +167,49 @@ where
         Ok(())
     }
 
-    /// Performs FFT on `a`
-    /// * `omega` - Special value `omega` is used for FFT over finite-fields
-    /// * `lgn` - Specifies log2 of number of elements
-    pub fn radix_fft(&mut self, a: &mut [E::Fr], omega: &E::Fr, lgn: u32) -> GPUResult<()> {
-        let n = 1 << lgn;
+    /// Runs a small throwaway transform, so that the one-time cost of the
+    /// first kernel launches (e.g. JIT compilation by the driver) is paid now
+    /// rather than by the first real FFT. Overwrites the device buffer.
+    pub fn warmup(&mut self) -> GPUResult<()> {
+        let lgn = cmp::min(MAX_RADIX_DEGREE, self.max_len().trailing_zeros());
+        let mut a = vec![E::Fr::one(); 1 << lgn];
+        self.upload(&a)?;
+        self.radix_fft_on_device(&E::Fr::one(), lgn)?;
+        self.mul_by_field_on_device(&E::Fr::one(), 1 << lgn)?;
+        self.download(&mut a)
+    }
+
+    /// Returns the number of elements the device buffer holds.
+    pub fn max_len(&self) -> usize {
+        self.fft_src_buffer.len()
+    }
 
+    /// Copies `a` into the device buffer that the on-device operations work on.
+    pub fn upload(&mut self, a: &[E::Fr]) -> GPUResult<()> {
+        let ta = unsafe { std::mem::transmute::<&[E::Fr], &[structs::PrimeFieldStruct<E::Fr>]>(a) };
+        self.fft_src_buffer.write(ta).enq()?;
+        Ok(())
+    }
+
+    /// Copies the first `a.len()` elements of the device buffer into `a`.
+    pub fn download(&mut self, a: &mut [E::Fr]) -> GPUResult<()> {
         let ta = unsafe {
             std::mem::transmute::<&mut [E::Fr], &mut [structs::PrimeFieldStruct<E::Fr>]>(a)
         };
+        self.fft_src_buffer.read(ta).enq()?;
+        self.proque.finish()?; // Wait for all commands in the queue (Including read command)
+        Ok(())
+    }
+
+    /// Performs FFT on the first `2^lgn` elements of the device buffer, see `upload`
+    /// * `omega` - Special value `omega` is used for FFT over finite-fields
+    /// * `lgn` - Specifies log2 of number of elements
+    pub fn radix_fft_on_device(&mut self, omega: &E::Fr, lgn: u32) -> GPUResult<()> {
+        let n = 1 << lgn;
 
         let max_deg = cmp::min(MAX_RADIX_DEGREE, lgn);
         self.setup_pq(omega, n, max_deg)?;
 
-        self.fft_src_buffer.write(&*ta).enq()?;
         let mut in_src = true;
         let mut lgp = 0u32;
         while lgp < lgn {
@@ -180,13 +218,116 @@ where
             lgp += deg;
             in_src = !in_src; // Destination of this FFT round is source of the next round.
         }
-        if in_src {
-            self.fft_src_buffer.read(ta).enq()?;
-        } else {
-            self.fft_dst_buffer.read(ta).enq()?;
+        if !in_src {
+            // Keep the result in the source buffer for subsequent operations
+            std::mem::swap(&mut self.fft_src_buffer, &mut self.fft_dst_buffer);
         }
-        self.proque.finish()?; // Wait for all commands in the queue (Including read command)
 
         Ok(())
     }
+
+    /// Multiplies the first `n` elements of the device buffer by `field`
+    pub fn mul_by_field_on_device(&mut self, field: &E::Fr, n: u32) -> GPUResult<()> {
+        if locks::PriorityLock::should_break(self.priority) {
+            return Err(GPUError::GPUTaken);
+        }
+
+        let kernel = self
+            .proque
+            .kernel_builder("mul_by_field")
+            .global_work_size([n])
+            .arg(&self.fft_src_buffer)
+            .arg(n)
+            .arg(structs::PrimeFieldStruct::<E::Fr>(*field))
+            .build()?;
+        unsafe {
+            kernel.enq()?;
+        } // Running a GPU kernel is unsafe!
+        Ok(())
+    }
+
+    /// Adds `other` to the first `other.len()` elements of the device buffer
+    pub fn add_assign_on_device(&mut self, other: &[E::Fr]) -> GPUResult<()> {
+        self.elementwise_on_device("add_elements", other)
+    }
+
+    /// Subtracts `other` from the first `other.len()` elements of the device buffer
+    pub fn sub_assign_on_device(&mut self, other: &[E::Fr]) -> GPUResult<()> {
+        self.elementwise_on_device("sub_elements", other)
+    }
+
+    /// Sets the device buffer aside, so that another vector can be uploaded and
+    /// transformed while this one stays on the device, see `mul_stashed_on_device`.
+    /// The device buffer is undefined afterwards. The stash is allocated on first use.
+    pub fn stash_on_device(&mut self) -> GPUResult<()> {
+        let stash = match self.fft_stash_buffer.take() {
+            Some(stash) => stash,
+            None => Buffer::builder()
+                .queue(self.proque.queue().clone())
+                .flags(MemFlags::new().read_write())
+                .len(self.max_len())
+                .build()?,
+        };
+        self.fft_stash_buffer = Some(std::mem::replace(&mut self.fft_src_buffer, stash));
+        Ok(())
+    }
+
+    /// Multiplies the first `n` elements of the device buffer by the ones set
+    /// aside with `stash_on_device`
+    pub fn mul_stashed_on_device(&mut self, n: u32) -> GPUResult<()> {
+        if locks::PriorityLock::should_break(self.priority) {
+            return Err(GPUError::GPUTaken);
+        }
+
+        let stash = self
+            .fft_stash_buffer
+            .as_ref()
+            .ok_or(GPUError::Simple("Nothing was stashed on the device!"))?;
+        self.run_elementwise("mul_elements", stash, n)
+    }
+
+    /// Runs the element-wise kernel `name` on the device buffer and `other`,
+    /// which is staged in the destination buffer (unused outside of FFT rounds)
+    fn elementwise_on_device(&mut self, name: &str, other: &[E::Fr]) -> GPUResult<()> {
+        if locks::PriorityLock::should_break(self.priority) {
+            return Err(GPUError::GPUTaken);
+        }
+
+        let n = other.len() as u32;
+        let tother =
+            unsafe { std::mem::transmute::<&[E::Fr], &[structs::PrimeFieldStruct<E::Fr>]>(other) };
+        self.fft_dst_buffer.write(tother).enq()?;
+
+        self.run_elementwise(name, &self.fft_dst_buffer, n)
+    }
+
+    /// Runs the element-wise kernel `name` on the first `n` elements of the
+    /// device buffer and `other`
+    fn run_elementwise(
+        &self,
+        name: &str,
+        other: &Buffer<structs::PrimeFieldStruct<E::Fr>>,
+        n: u32,
+    ) -> GPUResult<()> {
+        let kernel = self
+            .proque
+            .kernel_builder(name)
+            .global_work_size([n])
+            .arg(&self.fft_src_buffer)
+            .arg(other)
+            .build()?;
+        unsafe {
+            kernel.enq()?;
+        } // Running a GPU kernel is unsafe!
+        Ok(())
+    }
+
+    /// Performs FFT on `a`
+    /// * `omega` - Special value `omega` is used for FFT over finite-fields
+    /// * `lgn` - Specifies log2 of number of elements
+    pub fn radix_fft(&mut self, a: &mut [E::Fr], omega: &E::Fr, lgn: u32) -> GPUResult<()> {
+        self.upload(a)?;
+        self.radix_fft_on_device(omega, lgn)?;
+        self.download(a)
+    }
 }
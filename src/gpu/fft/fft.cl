@@ -73,4 +73,25 @@ __kernel void mul_by_field(__global FIELD* elements,
                         FIELD field) {
   uint gid = get_global_id(0);
   elements[gid] = FIELD_mul(elements[gid], field);
-}
\ No newline at end of file
+}
+
+/// Adds the elements of `other` to `elements`
+__kernel void add_elements(__global FIELD* elements,
+                        __global FIELD* other) {
+  uint gid = get_global_id(0);
+  elements[gid] = FIELD_add(elements[gid], other[gid]);
+}
+
+/// Subtracts the elements of `other` from `elements`
+__kernel void sub_elements(__global FIELD* elements,
+                        __global FIELD* other) {
+  uint gid = get_global_id(0);
+  elements[gid] = FIELD_sub(elements[gid], other[gid]);
+}
+
+/// Multiplies the elements of `other` into `elements`
+__kernel void mul_elements(__global FIELD* elements,
+                        __global FIELD* other) {
+  uint gid = get_global_id(0);
+  elements[gid] = FIELD_mul(elements[gid], other[gid]);
+}
@@ -136,6 +136,14 @@ macro_rules! locked_kernel {
                     }
                 }
             }
+
+            /// Returns the kernel, initializing it if necessary, e.g. to chain
+            /// operations on data kept in its device memory. Unlike `with`, this
+            /// doesn't retry once the GPU is taken by a high priority process.
+            pub fn get_kernel(&mut self) -> GPUResult<&mut $kern<E>> {
+                self.init();
+                self.kernel.as_mut().ok_or(GPUError::KernelUninitialized)
+            }
         }
     };
 }
@@ -42,3 +42,17 @@ use ocl::Device;
 lazy_static::lazy_static! {
     pub static ref GPU_NVIDIA_DEVICES: Vec<Device> = get_devices(GPU_NVIDIA_PLATFORM_NAME).unwrap_or_default();
 }
+
+/// Returns the number of GPUs that kernels may be created on. This only
+/// enumerates the devices, no kernel is compiled and no memory is allocated.
+#[cfg(feature = "gpu")]
+pub fn device_count() -> usize {
+    GPU_NVIDIA_DEVICES.len()
+}
+
+/// Returns whether GPU acceleration is available, i.e. whether there is at
+/// least one usable device and it wasn't disabled via `BELLMAN_NO_GPU`.
+#[cfg(feature = "gpu")]
+pub fn is_available() -> bool {
+    device_count() > 0
+}
@@ -7,6 +7,14 @@ use std::sync::Arc;
 
 // This module is compiled instead of `fft.rs` and `multiexp.rs` if `gpu` feature is disabled.
 
+pub fn device_count() -> usize {
+    0
+}
+
+pub fn is_available() -> bool {
+    false
+}
+
 pub struct FFTKernel<E>(PhantomData<E>)
 where
     E: ScalarEngine;
@@ -15,6 +23,10 @@ impl<E> FFTKernel<E>
 where
     E: ScalarEngine,
 {
+    pub fn memory_required(_: usize) -> usize {
+        0
+    }
+
     pub fn create(_: u32, _: bool) -> GPUResult<FFTKernel<E>> {
         return Err(GPUError::Simple("GPU accelerator is not enabled!"));
     }
@@ -22,6 +34,46 @@ where
     pub fn radix_fft(&mut self, _: &mut [E::Fr], _: &E::Fr, _: u32) -> GPUResult<()> {
         return Err(GPUError::Simple("GPU accelerator is not enabled!"));
     }
+
+    pub fn warmup(&mut self) -> GPUResult<()> {
+        return Err(GPUError::Simple("GPU accelerator is not enabled!"));
+    }
+
+    pub fn max_len(&self) -> usize {
+        0
+    }
+
+    pub fn upload(&mut self, _: &[E::Fr]) -> GPUResult<()> {
+        return Err(GPUError::Simple("GPU accelerator is not enabled!"));
+    }
+
+    pub fn download(&mut self, _: &mut [E::Fr]) -> GPUResult<()> {
+        return Err(GPUError::Simple("GPU accelerator is not enabled!"));
+    }
+
+    pub fn radix_fft_on_device(&mut self, _: &E::Fr, _: u32) -> GPUResult<()> {
+        return Err(GPUError::Simple("GPU accelerator is not enabled!"));
+    }
+
+    pub fn mul_by_field_on_device(&mut self, _: &E::Fr, _: u32) -> GPUResult<()> {
+        return Err(GPUError::Simple("GPU accelerator is not enabled!"));
+    }
+
+    pub fn add_assign_on_device(&mut self, _: &[E::Fr]) -> GPUResult<()> {
+        return Err(GPUError::Simple("GPU accelerator is not enabled!"));
+    }
+
+    pub fn sub_assign_on_device(&mut self, _: &[E::Fr]) -> GPUResult<()> {
+        return Err(GPUError::Simple("GPU accelerator is not enabled!"));
+    }
+
+    pub fn stash_on_device(&mut self) -> GPUResult<()> {
+        return Err(GPUError::Simple("GPU accelerator is not enabled!"));
+    }
+
+    pub fn mul_stashed_on_device(&mut self, _: u32) -> GPUResult<()> {
+        return Err(GPUError::Simple("GPU accelerator is not enabled!"));
+    }
 }
 
 pub struct MultiexpKernel<E>(PhantomData<E>)
@@ -54,7 +106,7 @@ where
 use crate::bls::Engine;
 
 macro_rules! locked_kernel {
-    ($class:ident) => {
+    ($class:ident, $kern:ident) => {
         pub struct $class<E>(PhantomData<E>);
 
         impl<E> $class<E>
@@ -71,9 +123,13 @@ macro_rules! locked_kernel {
             {
                 return Err(GPUError::Simple("GPU accelerator is not enabled!"));
             }
+
+            pub fn get_kernel(&mut self) -> GPUResult<&mut $kern<E>> {
+                return Err(GPUError::Simple("GPU accelerator is not enabled!"));
+            }
         }
     };
 }
 
-locked_kernel!(LockedFFTKernel);
-locked_kernel!(LockedMultiexpKernel);
+locked_kernel!(LockedFFTKernel, FFTKernel);
+locked_kernel!(LockedMultiexpKernel, MultiexpKernel);
@@ -139,6 +139,7 @@
 #[macro_use]
 extern crate hex_literal;
 
+pub mod batch_inversion;
 pub mod bls;
 pub mod domain;
 pub mod gadgets;
@@ -334,6 +335,15 @@ pub enum SynthesisError {
     /// During synthesis, our polynomials ended up being too high of degree
     #[error("polynomial degree is too large")]
     PolynomialDegreeTooLarge,
+    /// During polynomial arithmetic, an evaluation domain had an unsupported size
+    #[error("invalid evaluation domain size")]
+    InvalidDomainSize,
+    /// During polynomial arithmetic, an evaluation domain was in the wrong form
+    #[error("evaluation domain is not in the expected form")]
+    InvalidDomainForm,
+    /// During polynomial arithmetic, memory could not be allocated
+    #[error("out of memory")]
+    OutOfMemory,
     /// During proof generation, we encountered an identity in the CRS
     #[error("encountered an identity element in the CRS")]
     UnexpectedIdentity,
@@ -7,6 +7,7 @@
 
 use crossbeam_channel::{bounded, Receiver};
 use lazy_static::lazy_static;
+use rayon::prelude::*;
 use std::env;
 
 lazy_static! {
@@ -32,11 +33,20 @@ lazy_static! {
 #[derive(Clone)]
 pub struct Worker {}
 
+/// The worker returned by `Worker::current`.
+static CURRENT: Worker = Worker {};
+
 impl Worker {
     pub fn new() -> Worker {
         Worker {}
     }
 
+    /// Returns the process-wide default worker, for callers that don't manage
+    /// their own. It runs on the global `THREAD_POOL`, like any `Worker::new()`.
+    pub fn current() -> &'static Worker {
+        &CURRENT
+    }
+
     pub fn log_num_cpus(&self) -> u32 {
         log2_floor(*NUM_CPUS)
     }
@@ -60,13 +70,43 @@ impl Worker {
         F: FnOnce(&rayon::Scope<'a>, usize) -> R + Send,
         R: Send,
     {
-        let chunk_size = if elements < *NUM_CPUS {
+        self.scope_with_chunk_size(self.get_chunk_size(elements), f)
+    }
+
+    /// Like `scope`, but hands `chunk_size` to `f` instead of splitting the
+    /// elements evenly across the threads.
+    pub fn scope_with_chunk_size<'a, F, R>(&self, chunk_size: usize, f: F) -> R
+    where
+        F: FnOnce(&rayon::Scope<'a>, usize) -> R + Send,
+        R: Send,
+    {
+        THREAD_POOL.scope(|scope| f(scope, chunk_size))
+    }
+
+    /// Runs `f(i, chunk)` on the `i`-th chunk of `v`, for chunks of the size
+    /// `scope` would use, in parallel. Unlike spawning a task per chunk in
+    /// `scope`, this doesn't allocate a boxed closure per chunk, which adds up
+    /// for many short parallel passes.
+    pub fn in_place_scope<T, F>(&self, v: &mut [T], f: F)
+    where
+        T: Send,
+        F: Fn(usize, &mut [T]) + Sync,
+    {
+        let chunk_size = self.get_chunk_size(v.len());
+        THREAD_POOL.install(|| {
+            v.par_chunks_mut(chunk_size)
+                .enumerate()
+                .for_each(|(i, chunk)| f(i, chunk))
+        });
+    }
+
+    /// Returns the chunk size `scope` uses to split `elements` across threads.
+    pub fn get_chunk_size(&self, elements: usize) -> usize {
+        if elements < *NUM_CPUS {
             1
         } else {
             elements / *NUM_CPUS
-        };
-
-        THREAD_POOL.scope(|scope| f(scope, chunk_size))
+        }
     }
 }
 
@@ -80,6 +120,11 @@ impl<T> Waiter<T> {
         self.receiver.recv().unwrap()
     }
 
+    /// Returns the result if it is ready, without blocking.
+    pub fn try_wait(&self) -> Option<T> {
+        self.receiver.try_recv().ok()
+    }
+
     /// One off sending.
     pub fn done(val: T) -> Self {
         let (sender, receiver) = bounded(1);
@@ -115,4 +160,20 @@ mod tests {
         assert_eq!(log2_floor(7), 2);
         assert_eq!(log2_floor(8), 3);
     }
+
+    #[test]
+    fn test_in_place_scope() {
+        let worker = Worker::new();
+
+        for len in 0..100 {
+            let chunk_size = worker.get_chunk_size(len);
+            let mut v = vec![0; len];
+            worker.in_place_scope(&mut v, |i, chunk| {
+                for (j, x) in chunk.iter_mut().enumerate() {
+                    *x += i * chunk_size + j;
+                }
+            });
+            assert_eq!(v, (0..len).collect::<Vec<_>>());
+        }
+    }
 }